@@ -0,0 +1,524 @@
+//! The hub speaks hue and saturation for color lights and color temperature in Kelvin for white
+//! spectrum lights. A [`Color`] can be created from sRGB, hex strings, HSV, CIE 1931 xy or a color
+//! temperature and converted to any of the others. Use
+//! [`Hub::set_color`](crate::Hub::set_color) to send it to a light in whatever form the light
+//! supports.
+use std::str::FromStr;
+
+use crate::device::{ColorMode, LightAttributes};
+use crate::Device;
+
+/// White point of sRGB (D65) in CIE 1931 xy, used when there's no chromaticity, f.ex. black.
+const WHITE_POINT: Xy = Xy {
+    x: 0.3127,
+    y: 0.3290,
+};
+
+/// A color in 8 bit sRGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+/// A color as hue in degrees between 0.0 and 360.0, and saturation and value between 0.0 and 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub hue: f64,
+    pub saturation: f64,
+    pub value: f64,
+}
+
+/// A chromaticity in the CIE 1931 color space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Xy {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A color in one of the supported color models. The model it was created with is kept so no
+/// precision is lost until it's converted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Rgb(Rgb),
+    Hsv(Hsv),
+    Xy(Xy),
+    /// Color temperature in Kelvin.
+    Kelvin(u16),
+}
+
+impl Color {
+    /// Create a [`Color`] from 8 bit sRGB components.
+    pub fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self::Rgb(Rgb { red, green, blue })
+    }
+
+    /// Create a [`Color`] from hue in degrees, and saturation and value between 0.0 and 1.0.
+    pub fn hsv(hue: f64, saturation: f64, value: f64) -> Self {
+        Self::Hsv(Hsv {
+            hue,
+            saturation,
+            value,
+        })
+    }
+
+    /// Create a [`Color`] from CIE 1931 xy coordinates.
+    pub fn xy(x: f64, y: f64) -> Self {
+        Self::Xy(Xy { x, y })
+    }
+
+    /// Create a [`Color`] from a color temperature in Kelvin.
+    pub fn kelvin(kelvin: u16) -> Self {
+        Self::Kelvin(kelvin)
+    }
+
+    /// Create a [`Color`] from a color temperature in mireds (micro reciprocal degrees).
+    pub fn mireds(mireds: u16) -> Self {
+        Self::Kelvin(mireds_to_kelvin(mireds))
+    }
+
+    /// Parse a hex string such as `#ff8800`, `ff8800` or `#f80`.
+    pub fn from_hex(hex: &str) -> Result<Self, crate::Error> {
+        let invalid = || crate::Error::InvalidColor(hex.to_string());
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let component = |range: std::ops::Range<usize>| u8::from_str_radix(&digits[range], 16);
+
+        let (red, green, blue) = match digits.len() {
+            3 => (
+                component(0..1).map_err(|_| invalid())? * 0x11,
+                component(1..2).map_err(|_| invalid())? * 0x11,
+                component(2..3).map_err(|_| invalid())? * 0x11,
+            ),
+            6 => (
+                component(0..2).map_err(|_| invalid())?,
+                component(2..4).map_err(|_| invalid())?,
+                component(4..6).map_err(|_| invalid())?,
+            ),
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self::rgb(red, green, blue))
+    }
+
+    /// The color as a lowercase hex string, f.ex. `#ff8800`.
+    pub fn to_hex(&self) -> String {
+        let Rgb { red, green, blue } = self.to_rgb();
+
+        format!("#{red:02x}{green:02x}{blue:02x}")
+    }
+
+    /// The color in 8 bit sRGB. Colors outside of the sRGB gamut are clipped.
+    pub fn to_rgb(&self) -> Rgb {
+        match *self {
+            Self::Rgb(rgb) => rgb,
+            Self::Hsv(hsv) => hsv_to_rgb(hsv),
+            Self::Xy(xy) => xy_to_rgb(xy),
+            Self::Kelvin(kelvin) => xy_to_rgb(kelvin_to_xy(kelvin)),
+        }
+    }
+
+    /// The color as hue, saturation and value.
+    pub fn to_hsv(&self) -> Hsv {
+        match *self {
+            Self::Hsv(hsv) => hsv,
+            _ => rgb_to_hsv(self.to_rgb()),
+        }
+    }
+
+    /// The chromaticity of the color in CIE 1931 xy.
+    pub fn to_xy(&self) -> Xy {
+        match *self {
+            Self::Xy(xy) => xy,
+            Self::Kelvin(kelvin) => kelvin_to_xy(kelvin),
+            _ => rgb_to_xy(self.to_rgb()),
+        }
+    }
+
+    /// The color temperature in Kelvin. For anything but a color temperature this is the
+    /// correlated color temperature, which is only meaningful for colors close to white.
+    pub fn to_kelvin(&self) -> u16 {
+        match *self {
+            Self::Kelvin(kelvin) => kelvin,
+            _ => xy_to_kelvin(self.to_xy()),
+        }
+    }
+
+    /// The color temperature in mireds (micro reciprocal degrees).
+    pub fn to_mireds(&self) -> u16 {
+        kelvin_to_mireds(self.to_kelvin())
+    }
+}
+
+impl FromStr for Color {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
+impl From<Rgb> for Color {
+    fn from(rgb: Rgb) -> Self {
+        Self::Rgb(rgb)
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(hsv: Hsv) -> Self {
+        Self::Hsv(hsv)
+    }
+}
+
+impl From<Xy> for Color {
+    fn from(xy: Xy) -> Self {
+        Self::Xy(xy)
+    }
+}
+
+impl LightAttributes {
+    /// The color currently in effect according to [`ColorMode`], either a color temperature or
+    /// hue and saturation at full brightness. Without a known color mode hue and saturation are
+    /// preferred over color temperature. Returns `None` for lights without color support.
+    pub fn current_color(&self) -> Option<Color> {
+        let hue_saturation = self
+            .color_hue
+            .zip(self.color_saturation)
+            .map(|(hue, saturation)| Color::hsv(hue, saturation, 1.0));
+        let temperature = self.color_temperature.map(Color::kelvin);
+
+        match self.color_mode {
+            Some(ColorMode::Color) => hue_saturation,
+            Some(ColorMode::Temperature) => temperature,
+            _ => hue_saturation.or(temperature),
+        }
+    }
+
+    /// Clamp a color temperature in Kelvin to what the light supports. The hub reports the
+    /// coldest temperature as min and the warmest as max, either order is accepted.
+    pub(crate) fn clamp_temperature(&self, kelvin: u16) -> u16 {
+        match (self.color_temperature_min, self.color_temperature_max) {
+            (Some(min), Some(max)) => kelvin.clamp(min.min(max), min.max(max)),
+            _ => kelvin,
+        }
+    }
+}
+
+impl Device {
+    /// The color currently in effect if the [`Device`] is a light, see
+    /// [`LightAttributes::current_color`].
+    pub fn current_color(&self) -> Option<Color> {
+        match self {
+            Device::Light(light) => light.attributes.current_color(),
+            _ => None,
+        }
+    }
+}
+
+fn mireds_to_kelvin(mireds: u16) -> u16 {
+    (1_000_000.0 / f64::from(mireds.max(1))).round().min(f64::from(u16::MAX)) as u16
+}
+
+fn kelvin_to_mireds(kelvin: u16) -> u16 {
+    (1_000_000.0 / f64::from(kelvin.max(1))).round().min(f64::from(u16::MAX)) as u16
+}
+
+fn hsv_to_rgb(hsv: Hsv) -> Rgb {
+    let hue = hsv.hue.rem_euclid(360.0) / 60.0;
+    let saturation = hsv.saturation.clamp(0.0, 1.0);
+    let value = hsv.value.clamp(0.0, 1.0);
+
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let m = value - chroma;
+
+    let (red, green, blue) = match hue as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    Rgb {
+        red: to_u8(red + m),
+        green: to_u8(green + m),
+        blue: to_u8(blue + m),
+    }
+}
+
+fn rgb_to_hsv(rgb: Rgb) -> Hsv {
+    let (red, green, blue) = (to_f64(rgb.red), to_f64(rgb.green), to_f64(rgb.blue));
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let chroma = max - min;
+
+    let hue = if chroma == 0.0 {
+        0.0
+    } else if max == red {
+        60.0 * ((green - blue) / chroma).rem_euclid(6.0)
+    } else if max == green {
+        60.0 * ((blue - red) / chroma + 2.0)
+    } else {
+        60.0 * ((red - green) / chroma + 4.0)
+    };
+
+    let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+
+    Hsv {
+        hue,
+        saturation,
+        value: max,
+    }
+}
+
+fn rgb_to_xy(rgb: Rgb) -> Xy {
+    let red = to_linear(to_f64(rgb.red));
+    let green = to_linear(to_f64(rgb.green));
+    let blue = to_linear(to_f64(rgb.blue));
+
+    let x = 0.4124564 * red + 0.3575761 * green + 0.1804375 * blue;
+    let y = 0.2126729 * red + 0.7151522 * green + 0.0721750 * blue;
+    let z = 0.0193339 * red + 0.1191920 * green + 0.9503041 * blue;
+
+    let sum = x + y + z;
+    if sum == 0.0 {
+        return WHITE_POINT;
+    }
+
+    Xy {
+        x: x / sum,
+        y: y / sum,
+    }
+}
+
+/// Convert a chromaticity to sRGB at the highest brightness that keeps every component in range.
+fn xy_to_rgb(xy: Xy) -> Rgb {
+    let Xy { x, y } = if xy.y > 0.0 { xy } else { WHITE_POINT };
+
+    let big_x = x / y;
+    let big_z = (1.0 - x - y) / y;
+
+    let red = (3.2404542 * big_x - 1.5371385 - 0.4985314 * big_z).max(0.0);
+    let green = (-0.9692660 * big_x + 1.8760108 + 0.0415560 * big_z).max(0.0);
+    let blue = (0.0556434 * big_x - 0.2040259 + 1.0572252 * big_z).max(0.0);
+
+    let max = red.max(green).max(blue);
+    let scale = if max > 0.0 { max } else { 1.0 };
+
+    Rgb {
+        red: to_u8(from_linear(red / scale)),
+        green: to_u8(from_linear(green / scale)),
+        blue: to_u8(from_linear(blue / scale)),
+    }
+}
+
+/// Approximate the Planckian locus with the cubic splines from Kim et al. Valid between 1667 K
+/// and 25000 K, anything outside is clamped.
+fn kelvin_to_xy(kelvin: u16) -> Xy {
+    let t = f64::from(kelvin).clamp(1667.0, 25000.0);
+    let (t2, t3) = (t * t, t * t * t);
+
+    let x = if t <= 4000.0 {
+        -0.2661239e9 / t3 - 0.2343589e6 / t2 + 0.8776956e3 / t + 0.179910
+    } else {
+        -3.0258469e9 / t3 + 2.1070379e6 / t2 + 0.2226347e3 / t + 0.240390
+    };
+
+    let (x2, x3) = (x * x, x * x * x);
+
+    let y = if t <= 2222.0 {
+        -1.1063814 * x3 - 1.34811020 * x2 + 2.18555832 * x - 0.20219683
+    } else if t <= 4000.0 {
+        -0.9549476 * x3 - 1.37418593 * x2 + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x3 - 5.87338670 * x2 + 3.75112997 * x - 0.37001483
+    };
+
+    Xy { x, y }
+}
+
+/// Correlated color temperature using McCamy's approximation.
+fn xy_to_kelvin(xy: Xy) -> u16 {
+    let n = (xy.x - 0.3320) / (0.1858 - xy.y);
+    let kelvin = 449.0 * n.powi(3) + 3525.0 * n.powi(2) + 6823.3 * n + 5520.33;
+
+    kelvin.round().clamp(1.0, f64::from(u16::MAX)) as u16
+}
+
+fn to_linear(component: f64) -> f64 {
+    if component <= 0.04045 {
+        component / 12.92
+    } else {
+        ((component + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(component: f64) -> f64 {
+    if component <= 0.0031308 {
+        component * 12.92
+    } else {
+        1.055 * component.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_f64(component: u8) -> f64 {
+    f64::from(component) / 255.0
+}
+
+fn to_u8(component: f64) -> u8 {
+    (component.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not within {tolerance} of {expected}"
+        );
+    }
+
+    fn light_with(attributes: serde_json::Value) -> LightAttributes {
+        let mut device: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/light.json")).unwrap();
+        device["attributes"]
+            .as_object_mut()
+            .unwrap()
+            .extend(attributes.as_object().unwrap().clone());
+
+        match serde_json::from_value(device).unwrap() {
+            Device::Light(light) => light.attributes,
+            device => panic!("not a light: {device:?}"),
+        }
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(Color::from_hex("#ff8800").unwrap(), Color::rgb(255, 136, 0));
+        assert_eq!(Color::from_hex("FF8800").unwrap(), Color::rgb(255, 136, 0));
+        assert_eq!(Color::from_hex("#f80").unwrap(), Color::rgb(255, 136, 0));
+        assert_eq!("#0a0b0c".parse::<Color>().unwrap().to_hex(), "#0a0b0c");
+        assert_eq!(Color::rgb(255, 136, 0).to_hex(), "#ff8800");
+
+        for invalid in ["", "#", "#ff88", "#ff88000", "#gg8800", "#ff88 0"] {
+            assert!(Color::from_hex(invalid).is_err(), "{invalid:?} was parsed");
+        }
+    }
+
+    #[test]
+    fn hsv() {
+        let red = Color::from_hex("#ff0000").unwrap().to_hsv();
+        assert_close(red.hue, 0.0, 1e-9);
+        assert_close(red.saturation, 1.0, 1e-9);
+        assert_close(red.value, 1.0, 1e-9);
+
+        let orange = Color::rgb(255, 136, 0).to_hsv();
+        assert_close(orange.hue, 32.0, 0.1);
+
+        assert_eq!(Color::hsv(120.0, 1.0, 1.0).to_rgb(), Rgb { red: 0, green: 255, blue: 0 });
+        assert_eq!(Color::hsv(240.0, 1.0, 1.0).to_rgb(), Rgb { red: 0, green: 0, blue: 255 });
+        assert_eq!(Color::hsv(0.0, 0.0, 1.0).to_hex(), "#ffffff");
+        assert_eq!(Color::hsv(360.0, 1.0, 1.0).to_hex(), "#ff0000");
+    }
+
+    #[test]
+    fn hsv_roundtrip() {
+        for hex in ["#ff0000", "#ff8800", "#123456", "#7f7f7f", "#00ffcc", "#000000", "#ffffff"] {
+            let hsv = Color::from_hex(hex).unwrap().to_hsv();
+            assert_eq!(Color::from(hsv).to_hex(), hex);
+        }
+    }
+
+    #[test]
+    fn xy() {
+        let white = Color::rgb(255, 255, 255).to_xy();
+        assert_close(white.x, 0.3127, 1e-3);
+        assert_close(white.y, 0.3290, 1e-3);
+
+        let red = Color::rgb(255, 0, 0).to_xy();
+        assert_close(red.x, 0.64, 1e-3);
+        assert_close(red.y, 0.33, 1e-3);
+
+        // Black has no chromaticity and falls back to the white point.
+        assert_eq!(Color::rgb(0, 0, 0).to_xy(), WHITE_POINT);
+    }
+
+    #[test]
+    fn xy_roundtrip() {
+        for hex in ["#ff0000", "#00ff00", "#0000ff", "#ff8800", "#ffffff", "#336699"] {
+            let expected = Color::from_hex(hex).unwrap().to_xy();
+            let rgb = Color::from(expected).to_rgb();
+
+            // xy carries no brightness, compare the chromaticity.
+            let xy = Color::from(rgb).to_xy();
+            assert_close(xy.x, expected.x, 2e-3);
+            assert_close(xy.y, expected.y, 2e-3);
+        }
+    }
+
+    #[test]
+    fn mireds() {
+        assert_eq!(Color::kelvin(2700).to_mireds(), 370);
+        assert_eq!(Color::kelvin(6500).to_mireds(), 154);
+        assert_eq!(Color::mireds(500).to_kelvin(), 2000);
+        assert_eq!(Color::mireds(153).to_kelvin(), 6536);
+
+        for kelvin in [2000, 2202, 2700, 4000] {
+            let back = Color::mireds(Color::kelvin(kelvin).to_mireds()).to_kelvin();
+            assert!(back.abs_diff(kelvin) <= kelvin / 100, "{kelvin} came back as {back}");
+        }
+    }
+
+    #[test]
+    fn planckian_locus() {
+        let warm = Color::kelvin(2700).to_xy();
+        assert_close(warm.x, 0.4599, 1e-3);
+        assert_close(warm.y, 0.4106, 1e-3);
+
+        let cold = Color::kelvin(6500).to_xy();
+        assert_close(cold.x, 0.3135, 1e-3);
+        assert_close(cold.y, 0.3237, 1e-3);
+    }
+
+    #[test]
+    fn mccamy() {
+        // D65 is close to but not on the Planckian locus.
+        let d65 = Color::xy(0.3127, 0.3290).to_kelvin();
+        assert!(d65.abs_diff(6504) <= 5, "D65 came back as {d65}");
+
+        for kelvin in [2202, 2700, 3000, 4000, 5000, 6500] {
+            let back = Color::from(Color::kelvin(kelvin).to_xy()).to_kelvin();
+            assert!(back.abs_diff(kelvin) <= kelvin / 100, "{kelvin} came back as {back}");
+        }
+    }
+
+    #[test]
+    fn clamp_temperature() {
+        // The hub reports the coldest temperature as min.
+        let light = light_with(serde_json::json!({
+            "colorTemperatureMin": 4000,
+            "colorTemperatureMax": 2202,
+        }));
+
+        assert_eq!(light.clamp_temperature(6500), 4000);
+        assert_eq!(light.clamp_temperature(1800), 2202);
+        assert_eq!(light.clamp_temperature(3000), 3000);
+
+        let light = light_with(serde_json::json!({
+            "colorTemperatureMin": 2202,
+            "colorTemperatureMax": 4000,
+        }));
+
+        assert_eq!(light.clamp_temperature(6500), 4000);
+        assert_eq!(light.clamp_temperature(1800), 2202);
+    }
+}
@@ -1,45 +1,72 @@
 //! IKEA support multiple devices to be controlled via the Dirigera hub and they're divided into
 //! several types, in this code represented as the [Device] enum.
-use crate::deserialize_datetime;
+//!
+//! All types can be serialized back to the format used by the hub. Fields not known by this crate
+//! are kept in the `extra` maps so reading and writing a [`Device`] doesn't lose any data.
+use crate::{deserialize_datetime, serialize_datetime, serialize_number_optional};
+use crate::environment::{Celsius, Pm25, RelativeHumidity, VocIndex};
+use crate::ota::{OtaPolicy, OtaState, OtaStatus, ScheduleTime};
 use serde::{Deserialize, Serialize};
 
 /// A [`Device`] is a resource that is able to connect to the IKEA Dirigera hub - or the actual hub
-/// itself. It's represented as an enum with one variant for each type rather than separate types
-/// for each content since the data for the devices are shared.
-#[derive(Debug, Deserialize, PartialEq, PartialOrd)]
+/// itself. It's represented as an enum with one variant for each type where each variant holds
+/// the [`DeviceData`] shared by all devices and the attributes specific for that type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum Device {
-    Blinds(DeviceData),
-    Controller(DeviceData),
-    Gateway(DeviceData),
-    Light(DeviceData),
-    Outlet(DeviceData),
-    Sensor(DeviceData),
+    Blinds(TypedDevice<BlindsAttributes>),
+    Controller(TypedDevice<ControllerAttributes>),
+    Gateway(TypedDevice<GatewayAttributes>),
+    Light(TypedDevice<LightAttributes>),
+    Outlet(TypedDevice<OutletAttributes>),
+    #[serde(deserialize_with = "deserialize_sensor")]
+    Sensor(TypedDevice<SensorAttributes>),
     #[serde(untagged)]
-    Unknown(DeviceData),
+    Unknown(TypedDevice<CommonAttributes>),
+}
+
+/// A [`Device`] of a specific type, holding the shared [`DeviceData`] and the attributes for that
+/// type. It dereferences to the [`DeviceData`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedDevice<A> {
+    #[serde(flatten)]
+    pub data: DeviceData,
+    pub attributes: A,
 }
 
 /// Common data that is shared between all [`Device`]s.
-#[derive(Debug, Deserialize, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 #[serde(rename_all = "camelCase")]
 pub struct DeviceData {
     pub id: String,
     pub device_type: DeviceType,
-    #[serde(deserialize_with = "deserialize_datetime")]
+    #[serde(
+        deserialize_with = "deserialize_datetime",
+        serialize_with = "serialize_datetime"
+    )]
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub is_reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub is_hidden: Option<bool>,
-    #[serde(deserialize_with = "deserialize_datetime")]
+    #[serde(
+        deserialize_with = "deserialize_datetime",
+        serialize_with = "serialize_datetime"
+    )]
     pub last_seen: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub room: Option<Room>,
-    pub attributes: Attributes,
     pub remote_links: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_set: Option<Vec<DeviceSet>>,
     pub capabilities: Capabilities,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// A device can have capabilities it can send or receive. Each type is represented as a list of
 /// [`Capability`].
-#[derive(Debug, Deserialize, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 #[serde(rename_all = "camelCase")]
 pub struct Capabilities {
     pub can_send: Vec<Capability>,
@@ -48,7 +75,7 @@ pub struct Capabilities {
 
 /// Available capabilities across all devices that is listed either as something the device can
 /// send or receive.
-#[derive(Debug, Deserialize, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 #[serde(rename_all = "camelCase")]
 pub enum Capability {
     BlindsCurrentLevel,
@@ -60,6 +87,7 @@ pub enum Capability {
     Coordinates,
     CountryCode,
     CustomName,
+    Identify,
     IsOn,
     LightLevel,
     LogLevel,
@@ -67,20 +95,28 @@ pub enum Capability {
     Time,
     Timezone,
     UserConsents,
+    /// Any capability not known by this crate.
+    #[serde(untagged)]
+    Other(String),
 }
 
 /// A [`Device`] has both a `type` which is interpreted as the [`Device`] enum but also a
 /// `device_type`. They don't always overlap.
-#[derive(Debug, Deserialize, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 #[serde(rename_all = "camelCase")]
 pub enum DeviceType {
     LightController,
     Light,
     Gateway,
     MotionSensor,
+    EnvironmentSensor,
+    OpenCloseSensor,
     Outlet,
     Blinds,
     BlindsController,
+    /// Any device type not known by this crate.
+    #[serde(untagged)]
+    Other(String),
 }
 
 impl std::fmt::Display for DeviceType {
@@ -90,123 +126,641 @@ impl std::fmt::Display for DeviceType {
             Self::Light => f.pad("Light"),
             Self::Gateway => f.pad("Gateway"),
             Self::MotionSensor => f.pad("MotionSensor"),
+            Self::EnvironmentSensor => f.pad("EnvironmentSensor"),
+            Self::OpenCloseSensor => f.pad("OpenCloseSensor"),
             Self::Outlet => f.pad("Outlet"),
             Self::Blinds => f.pad("BlindsController"),
             Self::BlindsController => f.pad("BlindsController"),
+            Self::Other(device_type) => f.pad(device_type),
         }
     }
 }
 
 /// A device can start in different modes. It can start on, off, same as previous or toggled. This
 /// is used f.ex. after a power outage.
-#[derive(Debug, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 #[serde(rename_all = "camelCase")]
 pub enum Startup {
     StartOn,
     StartOff,
     StartPrevious,
     StartToggle,
+    /// Any startup behaviour not known by this crate.
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// Which way the motor of [`Device::Blinds`] is moving.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub enum BlindsState {
+    Up,
+    Down,
+    Stopped,
+    /// Any state not known by this crate.
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// Fields not known by this crate, kept so they're sent back unchanged. Dereferences to the
+/// underlying map.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct Extra(pub serde_json::Map<String, serde_json::Value>);
+
+impl PartialOrd for Extra {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        if self == other {
+            return Some(std::cmp::Ordering::Equal);
+        }
+
+        // Unknown fields have no natural order so they're compared by their JSON text. Maps that
+        // differ but print the same, f.ex. `0.0` and `-0.0`, are left unordered.
+        let this = serde_json::to_string(&self.0).ok()?;
+        let other = serde_json::to_string(&other.0).ok()?;
+
+        match this.cmp(&other) {
+            std::cmp::Ordering::Equal => None,
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl std::ops::Deref for Extra {
+    type Target = serde_json::Map<String, serde_json::Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for Extra {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Guard against removing a [`Device`] by accident, see
+/// [`Hub::remove_device`](crate::Hub::remove_device).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Removal<'a> {
+    /// Only remove the [`Device`] if its id matches the given one.
+    Confirm(&'a str),
+    /// Remove the [`Device`] without confirmation.
+    Force,
+}
+
+/// The color mode of a [`Device::Light`] tells which of the color attributes is in effect, either
+/// hue and saturation or color temperature.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub enum ColorMode {
+    Color,
+    Temperature,
+    /// Any color mode not known by this crate.
+    #[serde(untagged)]
+    Other(String),
 }
 
 /// The room which the [`Device`] is bound to. Icon and color represents what icon and color is
 /// selected in the IKEA [iPhone](https://apps.apple.com/se/app/ikea-home-smart/id1633226273) or
 /// [Android](https://play.google.com/store/apps/details?id=com.ikea.inter.homesmart.system2&hl=sv&pli=1)
 /// app.
-#[derive(Debug, Deserialize, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 #[serde(rename_all = "camelCase")]
 pub struct Room {
     pub id: String,
     pub name: String,
     pub color: String,
     pub icon: String,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-/// Each [`Device`] has attributes that's unique to the specific [`Device`]. Here however they're
-/// all represented in the same struct. Some of the attributes are common across all [`Device`] but
-/// the ones that are not are defined as optional.
-///
-/// <div class="warning">
-/// This is not optimal and will most likely change in a future version.
-/// </div>
-#[derive(Debug, Deserialize, PartialEq, PartialOrd)]
+/// A device set groups [`Device`]s across rooms so they can be controlled together, f.ex. all
+/// lights in a hallway spanning several floors.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 #[serde(rename_all = "camelCase")]
-pub struct Attributes {
+pub struct DeviceSet {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Attributes shared by all [`Device`]s, such as name, firmware and OTA information. Every type
+/// specific attribute struct embeds these as `common`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct CommonAttributes {
     pub custom_name: String,
     pub firmware_version: String,
     pub hardware_version: String,
     pub manufacturer: String,
     pub model: String,
-    pub ota_policy: String,
+    pub ota_policy: OtaPolicy,
     pub ota_progress: u8,
-    pub ota_schedule_end: String,
-    pub ota_schedule_start: String,
-    pub ota_state: String,
-    pub ota_status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ota_schedule_end: Option<ScheduleTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ota_schedule_start: Option<ScheduleTime>,
+    pub ota_state: OtaState,
+    pub ota_status: OtaStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub product_code: Option<String>,
     pub serial_number: String,
+    pub permitting_join: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
 
-    // Light, controller and outlet
-    pub is_on: Option<bool>,
-
-    // Light and outlet
-    pub startup_on_off: Option<Startup>,
-
-    // Light
+/// Attributes for a [`Device::Light`]. Color related attributes are only set for lights that
+/// support them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct LightAttributes {
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    pub is_on: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub light_level: Option<u8>,
-    pub permitting_join: bool,
-    pub color_mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startup_on_off: Option<Startup>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_mode: Option<ColorMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub color_temperature: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub color_temperature_min: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub color_temperature_max: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub startup_temperature: Option<i16>,
+    #[serde(
+        default,
+        serialize_with = "serialize_number_optional",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub color_hue: Option<f64>,
+    #[serde(
+        default,
+        serialize_with = "serialize_number_optional",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub color_saturation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub circadian_rhythm_mode: Option<String>,
+}
 
-    // Controller
+/// Attributes for a [`Device::Outlet`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct OutletAttributes {
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    pub is_on: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startup_on_off: Option<Startup>,
+}
+
+/// Attributes for a [`Device::Blinds`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct BlindsAttributes {
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    pub blinds_current_level: u8,
+    pub blinds_target_level: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blinds_state: Option<BlindsState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub battery_percentage: Option<i8>,
+}
 
-    // Blinds and controller
-    pub blinds_current_level: Option<u8>,
-    pub blinds_target_level: Option<u8>,
-    pub blinds_state: Option<String>,
+/// Attributes for a [`Device::Controller`] such as a remote or a light switch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct ControllerAttributes {
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_on: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_percentage: Option<i8>,
+}
+
+/// Attributes for a [`Device::Gateway`], the hub itself.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayAttributes {
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+}
 
-    // Environment sensor
-    pub current_temperature: Option<u8>,
-    pub current_r_h: Option<u8>,
-    pub current_p_m25: Option<u8>,
-    pub max_measured_p_m25: Option<u8>,
-    pub min_measured_p_m25: Option<u8>,
-    pub voc_index: Option<u8>,
+/// Attributes for a [`Device::Sensor`]. The hub uses the same type for all sensors so the
+/// variant is picked from the [`DeviceType`] of the [`Device`].
+#[derive(Debug, Clone, Serialize, PartialEq, PartialOrd)]
+#[serde(untagged)]
+pub enum SensorAttributes {
+    EnvironmentSensor(EnvironmentSensorAttributes),
+    OpenCloseSensor(OpenCloseSensorAttributes),
+    MotionSensor(MotionSensorAttributes),
+}
 
-    // Open and close sensor
-    pub is_open: Option<bool>,
+/// Attributes for an environment sensor such as VINDSTYRKA. See [`environment`](crate::environment)
+/// for the reading types.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentSensorAttributes {
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    pub current_temperature: Celsius,
+    pub current_r_h: RelativeHumidity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_p_m25: Option<Pm25>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_measured_p_m25: Option<Pm25>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_measured_p_m25: Option<Pm25>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voc_index: Option<VocIndex>,
+}
+
+/// Attributes for an open and close sensor such as PARASOLL.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenCloseSensorAttributes {
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    pub is_open: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_percentage: Option<i8>,
+}
+
+/// Attributes for a motion sensor.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub struct MotionSensorAttributes {
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_on: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_percentage: Option<i8>,
+}
+
+/// Deserialize the attributes of a [`Device::Sensor`] based on its [`DeviceType`]. Sensors of an
+/// unknown type fail here and are read as [`Device::Unknown`] instead.
+fn deserialize_sensor<'de, D>(deserializer: D) -> Result<TypedDevice<SensorAttributes>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let TypedDevice { data, attributes } =
+        TypedDevice::<serde_json::Value>::deserialize(deserializer)?;
+
+    let attributes = match data.device_type {
+        DeviceType::EnvironmentSensor => {
+            serde_json::from_value(attributes).map(SensorAttributes::EnvironmentSensor)
+        }
+        DeviceType::OpenCloseSensor => {
+            serde_json::from_value(attributes).map(SensorAttributes::OpenCloseSensor)
+        }
+        DeviceType::MotionSensor => {
+            serde_json::from_value(attributes).map(SensorAttributes::MotionSensor)
+        }
+        _ => {
+            return Err(D::Error::custom(format!(
+                "unknown sensor type {}",
+                data.device_type
+            )))
+        }
+    }
+    .map_err(D::Error::custom)?;
+
+    Ok(TypedDevice { data, attributes })
+}
+
+/// Run the same expression for the [`TypedDevice`] of every [`Device`] variant.
+macro_rules! each_device {
+    ($device:expr, $inner:ident => $body:expr) => {
+        match $device {
+            Device::Blinds($inner) => $body,
+            Device::Controller($inner) => $body,
+            Device::Gateway($inner) => $body,
+            Device::Light($inner) => $body,
+            Device::Outlet($inner) => $body,
+            Device::Sensor($inner) => $body,
+            Device::Unknown($inner) => $body,
+        }
+    };
 }
 
 impl Device {
     /// Get a reference to the [`DeviceData`] for the [`Device`].
     pub fn inner(&self) -> &DeviceData {
-        match self {
-            Device::Blinds(inner) => inner,
-            Device::Controller(inner) => inner,
-            Device::Gateway(inner) => inner,
-            Device::Light(inner) => inner,
-            Device::Outlet(inner) => inner,
-            Device::Sensor(inner) => inner,
-            Device::Unknown(inner) => inner,
-        }
+        each_device!(self, inner => &inner.data)
     }
 
     /// Get a mutable reference to the [`DeviceData`] for the [`Device`].
     pub fn inner_mut(&mut self) -> &mut DeviceData {
+        each_device!(self, inner => &mut inner.data)
+    }
+
+    /// Get a reference to the [`CommonAttributes`] for the [`Device`].
+    pub fn common_attributes(&self) -> &CommonAttributes {
+        each_device!(self, inner => inner.attributes.common())
+    }
+
+    /// Get a mutable reference to the [`CommonAttributes`] for the [`Device`].
+    pub fn common_attributes_mut(&mut self) -> &mut CommonAttributes {
+        each_device!(self, inner => inner.attributes.common_mut())
+    }
+
+    /// Whether the [`Device`] is on, for the types that can be turned on and off.
+    pub fn is_on(&self) -> Option<bool> {
         match self {
-            Device::Blinds(ref mut inner) => inner,
-            Device::Controller(ref mut inner) => inner,
-            Device::Gateway(ref mut inner) => inner,
-            Device::Light(ref mut inner) => inner,
-            Device::Outlet(ref mut inner) => inner,
-            Device::Sensor(ref mut inner) => inner,
-            Device::Unknown(ref mut inner) => inner,
+            Device::Light(light) => Some(light.attributes.is_on),
+            Device::Outlet(outlet) => Some(outlet.attributes.is_on),
+            Device::Controller(controller) => controller.attributes.is_on,
+            _ => None,
         }
     }
+
+    /// The battery level in percent, for battery powered [`Device`]s.
+    pub fn battery_percentage(&self) -> Option<i8> {
+        match self {
+            Device::Blinds(blinds) => blinds.attributes.battery_percentage,
+            Device::Controller(controller) => controller.attributes.battery_percentage,
+            Device::Sensor(sensor) => match &sensor.attributes {
+                SensorAttributes::OpenCloseSensor(attributes) => attributes.battery_percentage,
+                SensorAttributes::MotionSensor(attributes) => attributes.battery_percentage,
+                SensorAttributes::EnvironmentSensor(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether the [`Device`] can control the target, meaning it can send at least one
+    /// [`Capability`] the target can receive. Used to check remote links.
+    pub fn can_control(&self, target: &Device) -> bool {
+        let can_receive = &target.inner().capabilities.can_receive;
+
+        self.inner()
+            .capabilities
+            .can_send
+            .iter()
+            .any(|capability| can_receive.contains(capability))
+    }
+
+    /// Whether the [`Device`] is a member of the [`DeviceSet`] with the given id.
+    pub fn is_in_device_set(&self, device_set_id: &str) -> bool {
+        self.inner()
+            .device_set
+            .iter()
+            .flatten()
+            .any(|device_set| device_set.id == device_set_id)
+    }
+
+    /// Whether the [`Device`] belongs to the [`Room`] with the given id.
+    pub fn is_in_room(&self, room_id: &str) -> bool {
+        self.inner()
+            .room
+            .as_ref()
+            .is_some_and(|room| room.id == room_id)
+    }
+
+    /// Merge a partial set of attributes, such as the ones sent in a
+    /// [`DeviceState`](crate::events::DeviceState) event, into the attributes of the [`Device`].
+    pub fn merge_attributes(
+        &mut self,
+        patch: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), serde_json::Error> {
+        each_device!(self, inner => inner.attributes.merge(patch))
+    }
+}
+
+impl<A> std::ops::Deref for TypedDevice<A> {
+    type Target = DeviceData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl<A> std::ops::DerefMut for TypedDevice<A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
+}
+
+/// Shared behaviour for the type specific attributes of a [`Device`].
+pub trait DeviceAttributes {
+    /// Get a reference to the [`CommonAttributes`].
+    fn common(&self) -> &CommonAttributes;
+
+    /// Get a mutable reference to the [`CommonAttributes`].
+    fn common_mut(&mut self) -> &mut CommonAttributes;
+
+    /// Merge a partial set of attributes keyed by their camel cased name as used by the API.
+    /// Unknown keys are ignored.
+    fn merge(
+        &mut self,
+        patch: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), serde_json::Error>;
+}
+
+/// Assign each attribute present in the patch to the matching field, keyed by the camel cased
+/// name used by the API. Unknown keys are ignored.
+macro_rules! merge_attributes {
+    ($attributes:ident, $patch:ident, { $($key:literal => $field:ident),* $(,)? }) => {
+        for (key, value) in $patch {
+            match key.as_str() {
+                $($key => $attributes.$field = serde_json::from_value(value.clone())?,)*
+                _ => {}
+            }
+        }
+    };
+}
+
+/// Implement [`DeviceAttributes`] for a struct embedding [`CommonAttributes`] as `common`.
+macro_rules! device_attributes {
+    ($name:ident { $($key:literal => $field:ident),* $(,)? }) => {
+        impl DeviceAttributes for $name {
+            fn common(&self) -> &CommonAttributes {
+                &self.common
+            }
+
+            fn common_mut(&mut self) -> &mut CommonAttributes {
+                &mut self.common
+            }
+
+            #[allow(unused_variables)]
+            fn merge(
+                &mut self,
+                patch: &serde_json::Map<String, serde_json::Value>,
+            ) -> Result<(), serde_json::Error> {
+                self.common.merge(patch)?;
+                merge_attributes!(self, patch, { $($key => $field),* });
+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl DeviceAttributes for CommonAttributes {
+    fn common(&self) -> &CommonAttributes {
+        self
+    }
+
+    fn common_mut(&mut self) -> &mut CommonAttributes {
+        self
+    }
+
+    fn merge(
+        &mut self,
+        patch: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), serde_json::Error> {
+        merge_attributes!(self, patch, {
+            "customName" => custom_name,
+            "firmwareVersion" => firmware_version,
+            "hardwareVersion" => hardware_version,
+            "manufacturer" => manufacturer,
+            "model" => model,
+            "otaPolicy" => ota_policy,
+            "otaProgress" => ota_progress,
+            "otaScheduleEnd" => ota_schedule_end,
+            "otaScheduleStart" => ota_schedule_start,
+            "otaState" => ota_state,
+            "otaStatus" => ota_status,
+            "productCode" => product_code,
+            "serialNumber" => serial_number,
+            "permittingJoin" => permitting_join,
+        });
+
+        Ok(())
+    }
+}
+
+device_attributes!(LightAttributes {
+    "isOn" => is_on,
+    "lightLevel" => light_level,
+    "startupOnOff" => startup_on_off,
+    "colorMode" => color_mode,
+    "colorTemperature" => color_temperature,
+    "colorTemperatureMin" => color_temperature_min,
+    "colorTemperatureMax" => color_temperature_max,
+    "startupTemperature" => startup_temperature,
+    "colorHue" => color_hue,
+    "colorSaturation" => color_saturation,
+    "circadianRhythmMode" => circadian_rhythm_mode,
+});
+
+device_attributes!(OutletAttributes {
+    "isOn" => is_on,
+    "startupOnOff" => startup_on_off,
+});
+
+device_attributes!(BlindsAttributes {
+    "blindsCurrentLevel" => blinds_current_level,
+    "blindsTargetLevel" => blinds_target_level,
+    "blindsState" => blinds_state,
+    "batteryPercentage" => battery_percentage,
+});
+
+device_attributes!(ControllerAttributes {
+    "isOn" => is_on,
+    "batteryPercentage" => battery_percentage,
+});
+
+device_attributes!(GatewayAttributes {});
+
+device_attributes!(EnvironmentSensorAttributes {
+    "currentTemperature" => current_temperature,
+    "currentRH" => current_r_h,
+    "currentPM25" => current_p_m25,
+    "maxMeasuredPM25" => max_measured_p_m25,
+    "minMeasuredPM25" => min_measured_p_m25,
+    "vocIndex" => voc_index,
+});
+
+device_attributes!(OpenCloseSensorAttributes {
+    "isOpen" => is_open,
+    "batteryPercentage" => battery_percentage,
+});
+
+device_attributes!(MotionSensorAttributes {
+    "isOn" => is_on,
+    "batteryPercentage" => battery_percentage,
+});
+
+impl DeviceAttributes for SensorAttributes {
+    fn common(&self) -> &CommonAttributes {
+        match self {
+            Self::EnvironmentSensor(attributes) => attributes.common(),
+            Self::OpenCloseSensor(attributes) => attributes.common(),
+            Self::MotionSensor(attributes) => attributes.common(),
+        }
+    }
+
+    fn common_mut(&mut self) -> &mut CommonAttributes {
+        match self {
+            Self::EnvironmentSensor(attributes) => attributes.common_mut(),
+            Self::OpenCloseSensor(attributes) => attributes.common_mut(),
+            Self::MotionSensor(attributes) => attributes.common_mut(),
+        }
+    }
+
+    fn merge(
+        &mut self,
+        patch: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), serde_json::Error> {
+        match self {
+            Self::EnvironmentSensor(attributes) => attributes.merge(patch),
+            Self::OpenCloseSensor(attributes) => attributes.merge(patch),
+            Self::MotionSensor(attributes) => attributes.merge(patch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(device_type: &str) -> Device {
+        let fixture = include_str!("../tests/fixtures/environment_sensor.json");
+        let mut sensor: serde_json::Value = serde_json::from_str(fixture).unwrap();
+        sensor["deviceType"] = device_type.into();
+
+        serde_json::from_value(sensor).unwrap()
+    }
+
+    #[test]
+    fn sensor_attributes_from_device_type() {
+        assert!(matches!(
+            sensor("environmentSensor"),
+            Device::Sensor(TypedDevice {
+                attributes: SensorAttributes::EnvironmentSensor(_),
+                ..
+            })
+        ));
+
+        // Attributes of another kind of sensor don't change the variant picked from the type.
+        assert!(matches!(
+            sensor("motionSensor"),
+            Device::Sensor(TypedDevice {
+                attributes: SensorAttributes::MotionSensor(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn unknown_sensor_type() {
+        assert!(matches!(sensor("waterSensor"), Device::Unknown(_)));
+    }
 }
@@ -0,0 +1,438 @@
+//! Two reads of the same resource can be compared field by field. Each difference is represented
+//! as a [`Change`] addressed by the path to the field, f.ex. `attributes.is_on`. Use the `diff`
+//! method on [`Device`], [`DeviceData`], the attribute types, [`Room`] or [`SceneData`] to get
+//! the changes.
+use serde::Serialize;
+
+use crate::device::{
+    BlindsAttributes,
+    CommonAttributes,
+    ControllerAttributes,
+    EnvironmentSensorAttributes,
+    GatewayAttributes,
+    LightAttributes,
+    MotionSensorAttributes,
+    OpenCloseSensorAttributes,
+    OutletAttributes,
+    Room,
+    SensorAttributes,
+};
+use crate::scene::SceneData;
+use crate::Device;
+use crate::DeviceData;
+
+/// A single changed field with its value before and after the change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub path: String,
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
+}
+
+impl Change {
+    fn new<T: Serialize>(path: String, old: &T, new: &T) -> Self {
+        Self {
+            path,
+            old: serde_json::to_value(old).unwrap_or_default(),
+            new: serde_json::to_value(new).unwrap_or_default(),
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {} -> {}", self.path, self.old, self.new)
+    }
+}
+
+/// Push a [`Change`] for every listed field that differs between `$old` and `$new`.
+macro_rules! diff_fields {
+    ($changes:ident, $prefix:expr, $old:expr, $new:expr, [$($field:ident),* $(,)?]) => {
+        $(
+            if $old.$field != $new.$field {
+                $changes.push(Change::new(
+                    join($prefix, stringify!($field)),
+                    &$old.$field,
+                    &$new.$field,
+                ));
+            }
+        )*
+    };
+}
+
+fn join(prefix: &str, field: &str) -> String {
+    if prefix.is_empty() {
+        field.to_string()
+    } else {
+        format!("{prefix}.{field}")
+    }
+}
+
+impl DeviceData {
+    /// Compare with a later read of the same [`DeviceData`] and return every field that changed.
+    pub fn diff(&self, other: &Self) -> Vec<Change> {
+        let mut changes = Vec::new();
+        self.diff_into("", other, &mut changes);
+
+        changes
+    }
+
+    fn diff_into(&self, prefix: &str, other: &Self, changes: &mut Vec<Change>) {
+        diff_fields!(changes, prefix, self, other, [
+            id,
+            created_at,
+            is_reachable,
+            is_hidden,
+            last_seen,
+            remote_links,
+            device_set,
+            extra,
+        ]);
+
+        if self.device_type != other.device_type {
+            changes.push(Change {
+                path: join(prefix, "device_type"),
+                old: self.device_type.to_string().into(),
+                new: other.device_type.to_string().into(),
+            });
+        }
+
+        match (&self.room, &other.room) {
+            (Some(old), Some(new)) => old.diff_into(&join(prefix, "room"), new, changes),
+            (None, None) => {}
+            (old, new) => changes.push(Change {
+                path: join(prefix, "room"),
+                old: old.as_ref().map(|room| room.id.clone()).into(),
+                new: new.as_ref().map(|room| room.id.clone()).into(),
+            }),
+        }
+
+        let capabilities = join(prefix, "capabilities");
+        diff_fields!(changes, &capabilities, self.capabilities, other.capabilities, [
+            can_send,
+            can_receive,
+        ]);
+    }
+}
+
+impl Device {
+    /// Compare with a later read of the same [`Device`] and return every field that changed,
+    /// including the attributes for its type. If the type itself changed only that is reported.
+    pub fn diff(&self, other: &Self) -> Vec<Change> {
+        let mut changes = self.inner().diff(other.inner());
+
+        match (self, other) {
+            (Device::Blinds(old), Device::Blinds(new)) => {
+                old.attributes.diff_into("attributes", &new.attributes, &mut changes);
+            }
+            (Device::Controller(old), Device::Controller(new)) => {
+                old.attributes.diff_into("attributes", &new.attributes, &mut changes);
+            }
+            (Device::Gateway(old), Device::Gateway(new)) => {
+                old.attributes.diff_into("attributes", &new.attributes, &mut changes);
+            }
+            (Device::Light(old), Device::Light(new)) => {
+                old.attributes.diff_into("attributes", &new.attributes, &mut changes);
+            }
+            (Device::Outlet(old), Device::Outlet(new)) => {
+                old.attributes.diff_into("attributes", &new.attributes, &mut changes);
+            }
+            (Device::Sensor(old), Device::Sensor(new)) => {
+                old.attributes.diff_into("attributes", &new.attributes, &mut changes);
+            }
+            (Device::Unknown(old), Device::Unknown(new)) => {
+                old.attributes.diff_into("attributes", &new.attributes, &mut changes);
+            }
+            _ => changes.push(Change {
+                path: "type".to_string(),
+                old: kind(self).into(),
+                new: kind(other).into(),
+            }),
+        }
+
+        changes
+    }
+}
+
+/// Implement `diff` for type specific attributes embedding [`CommonAttributes`] as `common`.
+macro_rules! diff_attributes {
+    ($name:ident [$($field:ident),* $(,)?]) => {
+        impl $name {
+            /// Compare with a later read of the same attributes and return every field that
+            /// changed.
+            pub fn diff(&self, other: &Self) -> Vec<Change> {
+                let mut changes = Vec::new();
+                self.diff_into("", other, &mut changes);
+
+                changes
+            }
+
+            #[allow(unused_variables)]
+            fn diff_into(&self, prefix: &str, other: &Self, changes: &mut Vec<Change>) {
+                self.common.diff_into(prefix, &other.common, changes);
+                diff_fields!(changes, prefix, self, other, [$($field),*]);
+            }
+        }
+    };
+}
+
+impl CommonAttributes {
+    /// Compare with a later read of the same [`CommonAttributes`] and return every field that
+    /// changed.
+    pub fn diff(&self, other: &Self) -> Vec<Change> {
+        let mut changes = Vec::new();
+        self.diff_into("", other, &mut changes);
+
+        changes
+    }
+
+    fn diff_into(&self, prefix: &str, other: &Self, changes: &mut Vec<Change>) {
+        diff_fields!(changes, prefix, self, other, [
+            custom_name,
+            firmware_version,
+            hardware_version,
+            manufacturer,
+            model,
+            ota_policy,
+            ota_progress,
+            ota_schedule_end,
+            ota_schedule_start,
+            ota_state,
+            ota_status,
+            product_code,
+            serial_number,
+            permitting_join,
+            extra,
+        ]);
+    }
+}
+
+diff_attributes!(LightAttributes [
+    is_on,
+    light_level,
+    startup_on_off,
+    color_mode,
+    color_temperature,
+    color_temperature_min,
+    color_temperature_max,
+    startup_temperature,
+    color_hue,
+    color_saturation,
+    circadian_rhythm_mode,
+]);
+
+diff_attributes!(OutletAttributes [is_on, startup_on_off]);
+
+diff_attributes!(BlindsAttributes [
+    blinds_current_level,
+    blinds_target_level,
+    blinds_state,
+    battery_percentage,
+]);
+
+diff_attributes!(ControllerAttributes [is_on, battery_percentage]);
+
+diff_attributes!(GatewayAttributes []);
+
+diff_attributes!(EnvironmentSensorAttributes [
+    current_temperature,
+    current_r_h,
+    current_p_m25,
+    max_measured_p_m25,
+    min_measured_p_m25,
+    voc_index,
+]);
+
+diff_attributes!(OpenCloseSensorAttributes [is_open, battery_percentage]);
+
+diff_attributes!(MotionSensorAttributes [is_on, battery_percentage]);
+
+impl SensorAttributes {
+    /// Compare with a later read of the same [`SensorAttributes`] and return every field that
+    /// changed. If the kind of sensor changed only that is reported.
+    pub fn diff(&self, other: &Self) -> Vec<Change> {
+        let mut changes = Vec::new();
+        self.diff_into("", other, &mut changes);
+
+        changes
+    }
+
+    fn diff_into(&self, prefix: &str, other: &Self, changes: &mut Vec<Change>) {
+        match (self, other) {
+            (Self::EnvironmentSensor(old), Self::EnvironmentSensor(new)) => {
+                old.diff_into(prefix, new, changes);
+            }
+            (Self::OpenCloseSensor(old), Self::OpenCloseSensor(new)) => {
+                old.diff_into(prefix, new, changes);
+            }
+            (Self::MotionSensor(old), Self::MotionSensor(new)) => {
+                old.diff_into(prefix, new, changes);
+            }
+            _ => changes.push(Change {
+                path: join(prefix, "sensor"),
+                old: sensor_kind(self).into(),
+                new: sensor_kind(other).into(),
+            }),
+        }
+    }
+}
+
+fn kind(device: &Device) -> &'static str {
+    match device {
+        Device::Blinds(_) => "blinds",
+        Device::Controller(_) => "controller",
+        Device::Gateway(_) => "gateway",
+        Device::Light(_) => "light",
+        Device::Outlet(_) => "outlet",
+        Device::Sensor(_) => "sensor",
+        Device::Unknown(_) => "unknown",
+    }
+}
+
+fn sensor_kind(attributes: &SensorAttributes) -> &'static str {
+    match attributes {
+        SensorAttributes::EnvironmentSensor(_) => "environmentSensor",
+        SensorAttributes::OpenCloseSensor(_) => "openCloseSensor",
+        SensorAttributes::MotionSensor(_) => "motionSensor",
+    }
+}
+
+impl Room {
+    /// Compare with a later read of the same [`Room`] and return every field that changed.
+    pub fn diff(&self, other: &Self) -> Vec<Change> {
+        let mut changes = Vec::new();
+        self.diff_into("", other, &mut changes);
+
+        changes
+    }
+
+    fn diff_into(&self, prefix: &str, other: &Self, changes: &mut Vec<Change>) {
+        diff_fields!(changes, prefix, self, other, [id, name, color, icon, extra]);
+    }
+}
+
+impl SceneData {
+    /// Compare with a later read of the same [`SceneData`] and return every field that changed.
+    /// [`Action`](crate::scene::Action)s and [`Trigger`](crate::scene::Trigger)s are compared as
+    /// a whole.
+    pub fn diff(&self, other: &Self) -> Vec<Change> {
+        let mut changes = Vec::new();
+
+        diff_fields!(changes, "", self, other, [
+            id,
+            scene_type,
+            actions,
+            commands,
+            triggers,
+            undo_allowed_duration,
+            created_at,
+            last_completed,
+            last_triggered,
+            last_undo,
+            extra,
+        ]);
+
+        diff_fields!(changes, "info", self.info, other.info, [name, icon]);
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scene;
+
+    fn light() -> Device {
+        serde_json::from_str(include_str!("../tests/fixtures/light.json")).unwrap()
+    }
+
+    fn paths(changes: &[Change]) -> Vec<&str> {
+        changes.iter().map(|change| change.path.as_str()).collect()
+    }
+
+    #[test]
+    fn unchanged() {
+        assert!(light().diff(&light()).is_empty());
+    }
+
+    #[test]
+    fn device_data() {
+        let old = light();
+        let mut new = light();
+        new.inner_mut().is_reachable = false;
+        new.inner_mut().room.as_mut().unwrap().name = "Kitchen".to_string();
+
+        let changes = old.inner().diff(new.inner());
+
+        assert_eq!(paths(&changes), ["is_reachable", "room.name"]);
+        assert_eq!(changes[0].old, true);
+        assert_eq!(changes[0].new, false);
+        assert_eq!(changes[1].to_string(), r#"room.name: "Living room" -> "Kitchen""#);
+    }
+
+    #[test]
+    fn room_removed() {
+        let old = light();
+        let mut new = light();
+        new.inner_mut().room = None;
+
+        let changes = old.inner().diff(new.inner());
+
+        assert_eq!(paths(&changes), ["room"]);
+        assert_eq!(changes[0].old, "0c7a3e55-8d2c-4b7a-8f3e-6f2d1b9a4c20");
+        assert_eq!(changes[0].new, serde_json::Value::Null);
+    }
+
+    #[test]
+    fn attributes() {
+        let old = light();
+        let mut new = light();
+        new.merge_attributes(
+            serde_json::json!({ "customName": "Desk", "colorHue": 120 })
+                .as_object()
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            paths(&old.diff(&new)),
+            ["attributes.custom_name", "attributes.color_hue"]
+        );
+
+        let (Device::Light(old), Device::Light(new)) = (old, new) else {
+            panic!("not lights");
+        };
+
+        let changes = old.attributes.diff(&new.attributes);
+        assert_eq!(paths(&changes), ["custom_name", "color_hue"]);
+        assert_eq!(changes[1].old, 30.0);
+        assert_eq!(changes[1].new, 120.0);
+    }
+
+    #[test]
+    fn device_type_changed() {
+        let outlet: Device =
+            serde_json::from_str(include_str!("../tests/fixtures/outlet.json")).unwrap();
+
+        let changes = light().diff(&outlet);
+        let change = changes.iter().find(|change| change.path == "type").unwrap();
+
+        assert_eq!(change.old, "light");
+        assert_eq!(change.new, "outlet");
+        assert!(!changes.iter().any(|change| change.path.starts_with("attributes")));
+    }
+
+    #[test]
+    fn scene() {
+        let scene: Scene =
+            serde_json::from_str(include_str!("../tests/fixtures/scene.json")).unwrap();
+        let mut changed = scene.inner().clone();
+        changed.info.name = "Evening".to_string();
+        changed.undo_allowed_duration = 60;
+
+        let changes = scene.inner().diff(&changed);
+
+        assert_eq!(paths(&changes), ["undo_allowed_duration", "info.name"]);
+    }
+}
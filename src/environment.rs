@@ -0,0 +1,145 @@
+//! Environment sensors such as VINDSTYRKA report temperature, humidity, PM2.5 and a VOC index.
+//! Each reading is represented by its own unit type and [`AirQuality`] classifies the readings
+//! the same way as the sensor's own display.
+use serde::{Deserialize, Serialize};
+
+use crate::device::EnvironmentSensorAttributes;
+
+/// Implement a numeric reading in the given unit.
+macro_rules! reading {
+    ($(#[$meta:meta])* $name:ident, $unit:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Deserialize, PartialEq, PartialOrd)]
+        #[serde(transparent)]
+        pub struct $name(pub f64);
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                crate::serialize_number(self.0, serializer)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{} {}", self.0, $unit)
+            }
+        }
+    };
+}
+
+reading!(
+    /// Temperature in degrees Celsius.
+    Celsius,
+    "°C"
+);
+
+reading!(
+    /// Relative humidity in percent.
+    RelativeHumidity,
+    "%"
+);
+
+reading!(
+    /// Concentration of PM2.5 particles in µg/m³.
+    Pm25,
+    "µg/m³"
+);
+
+/// The Sensirion VOC index, where 100 is the average of the last 24 hours and the range is 1 to
+/// 500.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct VocIndex(pub u16);
+
+impl std::fmt::Display for VocIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Air quality classification, ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AirQuality {
+    Good,
+    Moderate,
+    Unhealthy,
+}
+
+impl Pm25 {
+    /// Classify the PM2.5 level using the same thresholds as VINDSTYRKA: up to 35 µg/m³ is good,
+    /// up to 85 µg/m³ is moderate and anything above is unhealthy.
+    pub fn air_quality(&self) -> AirQuality {
+        match self.0 {
+            level if level <= 35.0 => AirQuality::Good,
+            level if level <= 85.0 => AirQuality::Moderate,
+            _ => AirQuality::Unhealthy,
+        }
+    }
+}
+
+impl VocIndex {
+    /// Classify the VOC index using Sensirion's thresholds: up to 150 is good, up to 250 is
+    /// moderate and anything above is unhealthy.
+    pub fn air_quality(&self) -> AirQuality {
+        match self.0 {
+            0..=150 => AirQuality::Good,
+            151..=250 => AirQuality::Moderate,
+            _ => AirQuality::Unhealthy,
+        }
+    }
+}
+
+impl EnvironmentSensorAttributes {
+    /// The overall [`AirQuality`], which is the worst of the PM2.5 and VOC classifications. Returns
+    /// `None` if the sensor reports neither.
+    pub fn air_quality(&self) -> Option<AirQuality> {
+        let pm25 = self.current_p_m25.map(|pm25| pm25.air_quality());
+        let voc = self.voc_index.map(|voc| voc.air_quality());
+
+        pm25.max(voc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pm25_thresholds() {
+        assert_eq!(Pm25(0.0).air_quality(), AirQuality::Good);
+        assert_eq!(Pm25(35.0).air_quality(), AirQuality::Good);
+        assert_eq!(Pm25(35.1).air_quality(), AirQuality::Moderate);
+        assert_eq!(Pm25(85.0).air_quality(), AirQuality::Moderate);
+        assert_eq!(Pm25(85.1).air_quality(), AirQuality::Unhealthy);
+    }
+
+    #[test]
+    fn voc_index_thresholds() {
+        assert_eq!(VocIndex(1).air_quality(), AirQuality::Good);
+        assert_eq!(VocIndex(150).air_quality(), AirQuality::Good);
+        assert_eq!(VocIndex(151).air_quality(), AirQuality::Moderate);
+        assert_eq!(VocIndex(250).air_quality(), AirQuality::Moderate);
+        assert_eq!(VocIndex(251).air_quality(), AirQuality::Unhealthy);
+    }
+
+    #[test]
+    fn worst_of_readings() {
+        let fixture = include_str!("../tests/fixtures/environment_sensor.json");
+        let sensor: serde_json::Value = serde_json::from_str(fixture).unwrap();
+        let mut attributes: EnvironmentSensorAttributes =
+            serde_json::from_value(sensor["attributes"].clone()).unwrap();
+
+        attributes.current_p_m25 = Some(Pm25(90.0));
+        attributes.voc_index = Some(VocIndex(100));
+        assert_eq!(attributes.air_quality(), Some(AirQuality::Unhealthy));
+
+        attributes.current_p_m25 = None;
+        assert_eq!(attributes.air_quality(), Some(AirQuality::Good));
+
+        attributes.voc_index = None;
+        assert_eq!(attributes.air_quality(), None);
+    }
+}
@@ -20,5 +20,34 @@ pub enum Error {
     UrlParseError(#[from] ParseError),
     #[error("Could not find `code` in response.")]
     CodeNotFound,
+    #[error(transparent)]
+    WebSocketError(Box<tokio_tungstenite::tungstenite::Error>),
+    #[error("Connection closed by the hub.")]
+    ConnectionClosed,
+    #[error("No message from the hub within {0:?}.")]
+    ConnectionIdle(std::time::Duration),
+    #[error(transparent)]
+    TlsError(#[from] native_tls::Error),
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    #[error("Invalid color `{0}`.")]
+    InvalidColor(String),
+}
+
+
+/// A single [`Device`](crate::Device) returned by the hub that could not be parsed. Holds the raw
+/// JSON so it can be inspected or logged.
+#[derive(thiserror::Error, Debug)]
+#[error("Could not parse device {}: {source}", .id.as_deref().unwrap_or("without id"))]
+pub struct DeviceParseError {
+    pub id: Option<String>,
+    pub raw: serde_json::Value,
+    #[source]
+    pub source: serde_json::Error,
 }
 
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        Self::WebSocketError(Box::new(err))
+    }
+}
@@ -0,0 +1,438 @@
+//! Besides the RESTful API the hub exposes a WebSocket on the same port that pushes an event every
+//! time something changes, f.ex. when a light is toggled from the app or a scene is triggered.
+//! Each message is decoded into an [`Event`] and delivered through the stream returned by
+//! [`Hub::events`](crate::Hub::events).
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use futures::{SinkExt, Stream, StreamExt};
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use url_builder::{url_builder, Part};
+
+use crate::{deserialize_datetime_optional, serialize_datetime_optional};
+use crate::traits::DirigeraExt;
+use crate::Device;
+use crate::Error;
+use crate::Hub;
+use crate::Scene;
+use crate::DIRIGERA_API_VERSION;
+use crate::DIRIGERA_PORT;
+
+pub(crate) type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// How long [`Hub::subscribe`](crate::Hub::subscribe) waits without any message before it pings
+/// the hub.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long [`Hub::subscribe`](crate::Hub::subscribe) waits without any message, including the
+/// pong for a ping, before the connection is considered lost.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(75);
+
+/// An [`Event`] pushed from the hub. Events that carry a complete resource are deserialized into
+/// the existing [`Device`] and [`Scene`] types, events that only describe a change carry the
+/// changed parts.
+#[derive(Debug)]
+pub enum Event {
+    DeviceStateChanged(DeviceState),
+    DeviceAdded(Box<Device>),
+    DeviceRemoved(Reference),
+    SceneCreated(Scene),
+    SceneUpdated(Scene),
+    SceneDeleted(Reference),
+    SceneTriggered(Reference),
+    /// The connection to the hub was lost or a reconnect attempt failed. Only emitted by
+    /// [`Hub::subscribe`](crate::Hub::subscribe).
+    Disconnected(Error),
+    /// The connection to the hub is up again. Holds the full list of [`Device`]s fetched right
+    /// after reconnecting. Only emitted by [`Hub::subscribe`](crate::Hub::subscribe).
+    Reconnected(Vec<Device>),
+    /// Any event type not known by this crate. The `kind` is the raw `type` sent by the hub.
+    Unknown {
+        kind: String,
+        data: serde_json::Value,
+    },
+}
+
+/// A partial [`Device`] sent when the state of a device changes. Only the attributes that changed
+/// are included in `attributes`, keyed by their camel cased name as used by the API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceState {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_reachable: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_datetime_optional",
+        serialize_with = "serialize_datetime_optional",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_seen: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub attributes: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A reference to a resource by its id, used for events where only the id is of interest such as
+/// removed devices or triggered scenes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reference {
+    pub id: String,
+}
+
+/// Exponential backoff used between reconnect attempts. The first attempt waits `initial` and
+/// every following attempt waits `multiplier` times longer, capped at `max`.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+            multiplier: 2,
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before the reconnect attempt with the given zero based index.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial
+            .saturating_mul(self.multiplier.saturating_pow(attempt))
+            .min(self.max)
+    }
+}
+
+/// The envelope every message from the hub is wrapped in.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+impl Event {
+    /// Decode a single text message from the hub into an [`Event`].
+    pub fn from_message(message: &str) -> Result<Self, Error> {
+        let Envelope { kind, data } = serde_json::from_str(message)?;
+
+        let event = match kind.as_str() {
+            "deviceStateChanged" => Self::DeviceStateChanged(serde_json::from_value(data)?),
+            "deviceAdded" => Self::DeviceAdded(serde_json::from_value(data)?),
+            "deviceRemoved" => Self::DeviceRemoved(serde_json::from_value(data)?),
+            "sceneCreated" => Self::SceneCreated(serde_json::from_value(data)?),
+            "sceneUpdated" => Self::SceneUpdated(serde_json::from_value(data)?),
+            "sceneDeleted" => Self::SceneDeleted(serde_json::from_value(data)?),
+            "sceneTriggered" => Self::SceneTriggered(serde_json::from_value(data)?),
+            _ => Self::Unknown { kind, data },
+        };
+
+        Ok(event)
+    }
+}
+
+/// Open the authenticated WebSocket to the hub. Just like the HTTP client the hub's self signed
+/// certificate is accepted.
+pub(crate) async fn connect(
+    ip_address: Ipv4Addr,
+    authorization: &HeaderValue,
+) -> Result<Socket, Error> {
+    let url = url_builder! {
+        Part::Scheme("wss");
+        Part::HostIpv4(ip_address);
+        Part::Port(DIRIGERA_PORT);
+        Part::PathSlice(&[DIRIGERA_API_VERSION]);
+    }?;
+
+    let mut request = url.as_str().into_client_request()?;
+    request
+        .headers_mut()
+        .insert(AUTHORIZATION, authorization.clone());
+
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+
+    let (socket, _) = tokio_tungstenite::connect_async_tls_with_config(
+        request,
+        None,
+        false,
+        Some(tokio_tungstenite::Connector::NativeTls(connector)),
+    )
+    .await?;
+
+    Ok(socket)
+}
+
+/// Map a raw WebSocket message to an [`Event`]. Control frames such as ping and pong are handled
+/// by the socket itself and yield nothing.
+pub(crate) fn decode(
+    message: Result<Message, tokio_tungstenite::tungstenite::Error>,
+) -> Option<Result<Event, Error>> {
+    match message {
+        Ok(Message::Text(text)) => Some(Event::from_message(&text)),
+        Ok(_) => None,
+        Err(err) => Some(Err(err.into())),
+    }
+}
+
+/// State behind [`Hub::subscribe`](crate::Hub::subscribe).
+pub(crate) struct Subscription {
+    hub: Hub,
+    backoff: Backoff,
+    socket: Option<Socket>,
+    attempt: u32,
+    reconnecting: bool,
+    last_message: Instant,
+    next_ping: Instant,
+}
+
+impl Subscription {
+    pub(crate) fn new(hub: Hub, backoff: Backoff) -> Self {
+        Self {
+            hub,
+            backoff,
+            socket: None,
+            attempt: 0,
+            reconnecting: false,
+            last_message: Instant::now(),
+            next_ping: Instant::now(),
+        }
+    }
+
+    pub(crate) fn into_stream(self) -> impl Stream<Item = Result<Event, Error>> {
+        futures::stream::unfold(self, |mut subscription| async move {
+            let item = subscription.next().await;
+            Some((item, subscription))
+        })
+    }
+
+    async fn next(&mut self) -> Result<Event, Error> {
+        loop {
+            let Some(socket) = self.socket.as_mut() else {
+                if let Some(event) = self.connect().await {
+                    return Ok(event);
+                }
+
+                continue;
+            };
+
+            let deadline = self.next_ping.min(self.last_message + IDLE_TIMEOUT);
+            let Ok(message) = tokio::time::timeout_at(deadline, socket.next()).await else {
+                // A half-open connection never ends on its own, ping the hub and give up if
+                // nothing is received in time.
+                if Instant::now() >= self.last_message + IDLE_TIMEOUT {
+                    self.socket = None;
+                    return Ok(Event::Disconnected(Error::ConnectionIdle(IDLE_TIMEOUT)));
+                }
+
+                if let Err(err) = socket.send(Message::Ping(Vec::new())).await {
+                    self.socket = None;
+                    return Ok(Event::Disconnected(err.into()));
+                }
+
+                self.next_ping = Instant::now() + PING_INTERVAL;
+                continue;
+            };
+
+            self.last_message = Instant::now();
+            self.next_ping = self.last_message + PING_INTERVAL;
+
+            match message {
+                None => {
+                    self.socket = None;
+                    return Ok(Event::Disconnected(Error::ConnectionClosed));
+                }
+                Some(Err(err)) => {
+                    self.socket = None;
+                    return Ok(Event::Disconnected(err.into()));
+                }
+                Some(message) => {
+                    if let Some(event) = decode(message) {
+                        return event;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Connect to the hub. The initial connection is attempted right away and is silent, every
+    /// later attempt waits for the backoff and is reported as an [`Event`].
+    async fn connect(&mut self) -> Option<Event> {
+        if !self.reconnecting {
+            self.reconnecting = true;
+
+            return match self.hub.connect_events().await {
+                Ok(socket) => {
+                    self.connected(socket);
+                    None
+                }
+                Err(err) => Some(Event::Disconnected(err)),
+            };
+        }
+
+        tokio::time::sleep(self.backoff.delay(self.attempt)).await;
+        self.attempt = self.attempt.saturating_add(1);
+
+        let connected = async {
+            let socket = self.hub.connect_events().await?;
+            let devices = self.hub.list().await?;
+
+            Ok::<_, Error>((socket, devices))
+        }
+        .await;
+
+        match connected {
+            Ok((socket, devices)) => {
+                self.connected(socket);
+                self.attempt = 0;
+                Some(Event::Reconnected(devices))
+            }
+            Err(err) => Some(Event::Disconnected(err)),
+        }
+    }
+
+    fn connected(&mut self, socket: Socket) {
+        self.socket = Some(socket);
+        self.last_message = Instant::now();
+        self.next_ping = self.last_message + PING_INTERVAL;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(kind: &str, data: &str) -> String {
+        format!(r#"{{"id":"1","type":"{kind}","time":"2023-10-12T18:42:07.000Z","data":{data}}}"#)
+    }
+
+    #[test]
+    fn device_state_changed() {
+        let message = message(
+            "deviceStateChanged",
+            r#"{"id":"light_1","isReachable":true,"attributes":{"isOn":true,"lightLevel":40}}"#,
+        );
+
+        let Event::DeviceStateChanged(state) = Event::from_message(&message).unwrap() else {
+            panic!("not a state change");
+        };
+
+        assert_eq!(state.id, "light_1");
+        assert_eq!(state.is_reachable, Some(true));
+        assert_eq!(state.last_seen, None);
+        assert_eq!(state.attributes["isOn"], true);
+        assert_eq!(state.attributes["lightLevel"], 40);
+    }
+
+    #[test]
+    fn device_added() {
+        let message = message("deviceAdded", include_str!("../tests/fixtures/light.json"));
+
+        let Event::DeviceAdded(device) = Event::from_message(&message).unwrap() else {
+            panic!("not an added device");
+        };
+
+        assert!(matches!(*device, Device::Light(_)));
+    }
+
+    #[test]
+    fn scenes() {
+        let scene = include_str!("../tests/fixtures/scene.json");
+
+        assert!(matches!(
+            Event::from_message(&message("sceneCreated", scene)).unwrap(),
+            Event::SceneCreated(_)
+        ));
+        assert!(matches!(
+            Event::from_message(&message("sceneUpdated", scene)).unwrap(),
+            Event::SceneUpdated(_)
+        ));
+    }
+
+    #[test]
+    fn references() {
+        let reference = r#"{"id":"abc"}"#;
+
+        assert!(matches!(
+            Event::from_message(&message("deviceRemoved", reference)).unwrap(),
+            Event::DeviceRemoved(Reference { id }) if id == "abc"
+        ));
+        assert!(matches!(
+            Event::from_message(&message("sceneDeleted", reference)).unwrap(),
+            Event::SceneDeleted(_)
+        ));
+        assert!(matches!(
+            Event::from_message(&message("sceneTriggered", reference)).unwrap(),
+            Event::SceneTriggered(_)
+        ));
+    }
+
+    #[test]
+    fn unknown() {
+        let event = Event::from_message(&message("speakerPlaying", r#"{"volume":20}"#)).unwrap();
+
+        let Event::Unknown { kind, data } = event else {
+            panic!("not an unknown event");
+        };
+
+        assert_eq!(kind, "speakerPlaying");
+        assert_eq!(data, serde_json::json!({ "volume": 20 }));
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(Event::from_message("not json").is_err());
+        assert!(Event::from_message(r#"{"data":{}}"#).is_err());
+        assert!(Event::from_message(&message("deviceRemoved", r#"{"name":"abc"}"#)).is_err());
+    }
+
+    #[test]
+    fn backoff_grows() {
+        let backoff = Backoff::default();
+
+        assert_eq!(backoff.delay(0), Duration::from_secs(1));
+        assert_eq!(backoff.delay(1), Duration::from_secs(2));
+        assert_eq!(backoff.delay(5), Duration::from_secs(32));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let backoff = Backoff::default();
+
+        assert_eq!(backoff.delay(6), Duration::from_secs(60));
+        assert_eq!(backoff.delay(100), Duration::from_secs(60));
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn backoff_saturates() {
+        let backoff = Backoff {
+            initial: Duration::from_secs(u64::MAX / 2),
+            max: Duration::MAX,
+            multiplier: 3,
+        };
+
+        assert_eq!(backoff.delay(0), Duration::from_secs(u64::MAX / 2));
+        assert_eq!(backoff.delay(1), Duration::MAX);
+
+        let constant = Backoff {
+            initial: Duration::from_millis(250),
+            max: Duration::from_secs(1),
+            multiplier: 1,
+        };
+
+        assert_eq!(constant.delay(10), Duration::from_millis(250));
+    }
+}
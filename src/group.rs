@@ -0,0 +1,339 @@
+//! Besides rooms and device sets stored on the hub, a [`Group`] can be defined client-side, f.ex.
+//! from a configuration file, by device ids, rooms, device sets, types or any predicate. A command
+//! run for a [`Group`] is sent to all of its [`Device`]s in parallel and every [`Device`] gets its
+//! own result in the [`GroupReport`], so a single failing device doesn't stop the others.
+//!
+//! ```no_run
+//! # async fn example(hub: &dirigera::Hub) -> Result<(), dirigera::Error> {
+//! use dirigera::device::Capability;
+//! use dirigera::group::Group;
+//!
+//! let outdoor = Group::new().room("garden").device("light_1");
+//! let report = outdoor
+//!     .run(hub, &[Capability::LightLevel], |mut hub, mut device| async move {
+//!         hub.set_light_level(&mut device, 40).await?;
+//!         Ok(device)
+//!     })
+//!     .await?;
+//!
+//! for result in report.failed() {
+//!     eprintln!("{}: {:?}", result.id, result.outcome);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::future::Future;
+use std::sync::Arc;
+use futures::StreamExt;
+
+use crate::device::Capability;
+use crate::traits::DirigeraExt;
+use crate::Device;
+use crate::DeviceType;
+use crate::Hub;
+
+/// Number of commands that run at the same time unless set with [`Group::concurrency`].
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// A rule selecting which [`Device`]s are part of a [`Group`].
+#[derive(Clone)]
+pub enum Selector {
+    /// The [`Device`] with the given id.
+    Device(String),
+    /// Every [`Device`] in the [`Room`](crate::device::Room) with the given id.
+    Room(String),
+    /// Every [`Device`] in the [`DeviceSet`](crate::device::DeviceSet) with the given id.
+    DeviceSet(String),
+    /// Every [`Device`] of the given [`DeviceType`].
+    Type(DeviceType),
+    /// Every [`Device`] the predicate returns `true` for.
+    Filter(Arc<dyn Fn(&Device) -> bool + Send + Sync>),
+}
+
+impl Selector {
+    /// Whether the [`Device`] is selected.
+    pub fn matches(&self, device: &Device) -> bool {
+        match self {
+            Self::Device(id) => &device.inner().id == id,
+            Self::Room(id) => device.is_in_room(id),
+            Self::DeviceSet(id) => device.is_in_device_set(id),
+            Self::Type(device_type) => &device.inner().device_type == device_type,
+            Self::Filter(filter) => filter(device),
+        }
+    }
+}
+
+impl std::fmt::Debug for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Device(id) => f.debug_tuple("Device").field(id).finish(),
+            Self::Room(id) => f.debug_tuple("Room").field(id).finish(),
+            Self::DeviceSet(id) => f.debug_tuple("DeviceSet").field(id).finish(),
+            Self::Type(device_type) => f.debug_tuple("Type").field(device_type).finish(),
+            Self::Filter(_) => f.debug_tuple("Filter").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// A client-side group of [`Device`]s. A [`Device`] is part of the group if any of the
+/// [`Selector`]s matches it.
+#[derive(Debug, Clone)]
+pub struct Group {
+    selectors: Vec<Selector>,
+    concurrency: usize,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Group {
+    /// Create an empty [`Group`].
+    pub fn new() -> Self {
+        Self {
+            selectors: Vec::new(),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Add the [`Device`] with the given id.
+    pub fn device(self, id: &str) -> Self {
+        self.select(Selector::Device(id.to_string()))
+    }
+
+    /// Add every [`Device`] in the [`Room`](crate::device::Room) with the given id.
+    pub fn room(self, id: &str) -> Self {
+        self.select(Selector::Room(id.to_string()))
+    }
+
+    /// Add every [`Device`] in the [`DeviceSet`](crate::device::DeviceSet) with the given id.
+    pub fn device_set(self, id: &str) -> Self {
+        self.select(Selector::DeviceSet(id.to_string()))
+    }
+
+    /// Add every [`Device`] of the given [`DeviceType`].
+    pub fn device_type(self, device_type: DeviceType) -> Self {
+        self.select(Selector::Type(device_type))
+    }
+
+    /// Add every [`Device`] the predicate returns `true` for.
+    pub fn filter(self, filter: impl Fn(&Device) -> bool + Send + Sync + 'static) -> Self {
+        self.select(Selector::Filter(Arc::new(filter)))
+    }
+
+    /// Add a [`Selector`].
+    pub fn select(mut self, selector: Selector) -> Self {
+        self.selectors.push(selector);
+        self
+    }
+
+    /// Set how many commands may run at the same time, at least one.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Whether the [`Device`] is part of the [`Group`].
+    pub fn contains(&self, device: &Device) -> bool {
+        self.selectors.iter().any(|selector| selector.matches(device))
+    }
+
+    /// Run `command` for every [`Device`] in the [`Group`], as currently known by the [`Hub`].
+    /// [`Device`]s that can't receive all of the given `capabilities` or aren't reachable are
+    /// skipped. The command gets its own [`Hub`] and the [`Device`] and returns the updated
+    /// [`Device`], which makes any [`Hub`] method usable. Only listing the [`Device`]s can fail,
+    /// the outcome of every command is in the [`GroupReport`].
+    pub async fn run<C, Fut>(
+        &self,
+        hub: &Hub,
+        capabilities: &[Capability],
+        command: C,
+    ) -> Result<GroupReport, crate::Error>
+    where
+        C: Fn(Hub, Device) -> Fut,
+        Fut: Future<Output = anyhow::Result<Device>>,
+    {
+        let devices = hub
+            .list()
+            .await?
+            .into_iter()
+            .filter(|device| self.contains(device));
+
+        let command = &command;
+        let results = futures::stream::iter(devices)
+            .map(|device| async move {
+                let id = device.inner().id.clone();
+
+                let outcome = if !device.can_receive(capabilities) {
+                    Outcome::Unsupported
+                } else if !device.inner().is_reachable {
+                    Outcome::Unreachable
+                } else {
+                    match command(hub.clone(), device).await {
+                        Ok(device) => Outcome::Success(Box::new(device)),
+                        Err(err) => Outcome::from_error(err),
+                    }
+                };
+
+                DeviceResult { id, outcome }
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        Ok(GroupReport { results })
+    }
+
+    /// Turn every [`Device`] in the [`Group`] on or off, see [`Hub::set_on`].
+    pub async fn set_on(&self, hub: &Hub, is_on: bool) -> Result<GroupReport, crate::Error> {
+        self.run(hub, &[Capability::IsOn], |mut hub, mut device| async move {
+            hub.set_on(&mut device, is_on).await?;
+            Ok(device)
+        })
+        .await
+    }
+
+    /// Set the light level of every light in the [`Group`], see [`Hub::set_light_level`].
+    pub async fn set_light_level(&self, hub: &Hub, level: u8) -> Result<GroupReport, crate::Error> {
+        self.run(hub, &[Capability::LightLevel], |mut hub, mut device| async move {
+            hub.set_light_level(&mut device, level).await?;
+            Ok(device)
+        })
+        .await
+    }
+
+    /// Set a [`Color`](crate::Color) on every light in the [`Group`], see [`Hub::set_color`].
+    /// Lights without any color support are reported as [`Outcome::Unsupported`].
+    pub async fn set_color(
+        &self,
+        hub: &Hub,
+        color: crate::Color,
+    ) -> Result<GroupReport, crate::Error> {
+        self.run(hub, &[], |mut hub, mut device| async move {
+            let color_support = device.can_receive(&[Capability::ColorTemperature])
+                || device.can_receive(&[Capability::ColorHue, Capability::ColorSaturation]);
+
+            if !color_support {
+                return Err(Unsupported.into());
+            }
+
+            hub.set_color(&mut device, color).await?;
+            Ok(device)
+        })
+        .await
+    }
+}
+
+/// Returned from a [`Group::run`] command to report the [`Device`] as [`Outcome::Unsupported`].
+#[derive(thiserror::Error, Debug)]
+#[error("Device doesn't support the command.")]
+pub struct Unsupported;
+
+/// The outcome of a command for a single [`Device`] in a [`Group`].
+#[derive(Debug)]
+pub enum Outcome {
+    /// The command succeeded, with the updated [`Device`].
+    Success(Box<Device>),
+    /// The [`Device`] doesn't have the capabilities the command requires.
+    Unsupported,
+    /// The [`Device`] isn't reachable by the [`Hub`].
+    Unreachable,
+    /// The request to the [`Hub`] failed.
+    Http(reqwest::Error),
+    /// The command failed for any other reason, f.ex. a value out of range.
+    Failed(anyhow::Error),
+}
+
+impl Outcome {
+    fn from_error(err: anyhow::Error) -> Self {
+        if err.is::<Unsupported>() {
+            return Self::Unsupported;
+        }
+
+        let err = match err.downcast::<reqwest::Error>() {
+            Ok(err) => return Self::Http(err),
+            Err(err) => err,
+        };
+
+        match err.downcast::<crate::Error>() {
+            Ok(crate::Error::BuildError(err)) => Self::Http(err),
+            Ok(err) => Self::Failed(err.into()),
+            Err(err) => Self::Failed(err),
+        }
+    }
+
+    /// Whether the command succeeded.
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success(_))
+    }
+}
+
+/// The result of a command for a single [`Device`] in a [`Group`].
+#[derive(Debug)]
+pub struct DeviceResult {
+    /// Id of the [`Device`].
+    pub id: String,
+    pub outcome: Outcome,
+}
+
+/// Per [`Device`] results of running a command for a [`Group`].
+#[derive(Debug)]
+pub struct GroupReport {
+    pub results: Vec<DeviceResult>,
+}
+
+impl GroupReport {
+    /// Whether the command succeeded for every [`Device`] in the [`Group`].
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|result| result.outcome.is_success())
+    }
+
+    /// The results of the [`Device`]s the command succeeded for.
+    pub fn succeeded(&self) -> impl Iterator<Item = &DeviceResult> {
+        self.results.iter().filter(|result| result.outcome.is_success())
+    }
+
+    /// The results of the [`Device`]s the command didn't succeed for.
+    pub fn failed(&self) -> impl Iterator<Item = &DeviceResult> {
+        self.results.iter().filter(|result| !result.outcome.is_success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reqwest_error() -> reqwest::Error {
+        reqwest::Client::new().get("not a url").build().unwrap_err()
+    }
+
+    #[test]
+    fn outcome_unsupported() {
+        assert!(matches!(Outcome::from_error(Unsupported.into()), Outcome::Unsupported));
+    }
+
+    #[test]
+    fn outcome_http() {
+        assert!(matches!(Outcome::from_error(reqwest_error().into()), Outcome::Http(_)));
+
+        let err = crate::Error::BuildError(reqwest_error());
+        assert!(matches!(Outcome::from_error(err.into()), Outcome::Http(_)));
+    }
+
+    #[test]
+    fn outcome_failed() {
+        let err = crate::Error::InvalidColor("purple".to_string());
+        let Outcome::Failed(err) = Outcome::from_error(err.into()) else {
+            panic!("error should be kept as a failure");
+        };
+        assert!(matches!(
+            err.downcast_ref::<crate::Error>(),
+            Some(crate::Error::InvalidColor(_))
+        ));
+
+        let err = anyhow::anyhow!("level must be between 0.0 -> 100.0");
+        assert!(matches!(Outcome::from_error(err), Outcome::Failed(_)));
+    }
+}
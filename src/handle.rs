@@ -0,0 +1,271 @@
+//! The [`Hub`] checks the capabilities of a [`Device`] on every call. A handle does that check
+//! once, when it's created from the [`Device`], and only exposes the operations the device
+//! supports. Trying to set hue and saturation on a white spectrum light is then a compile error
+//! rather than a runtime failure.
+//!
+//! ```no_run
+//! # async fn example(hub: &mut dirigera::Hub, device: &mut dirigera::Device) -> anyhow::Result<()> {
+//! if let Some(mut light) = device.as_dimmable_light() {
+//!     light.set_light_level(hub, 40).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::device::{Capability, Startup};
+use crate::Device;
+use crate::Hub;
+
+/// Define a handle wrapping a mutable reference to a [`Device`].
+macro_rules! handle {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        pub struct $name<'a> {
+            device: &'a mut Device,
+        }
+
+        impl<'a> $name<'a> {
+            /// Get a reference to the underlying [`Device`].
+            pub fn device(&self) -> &Device {
+                self.device
+            }
+
+            /// Give back the mutable reference to the underlying [`Device`].
+            pub fn into_device(self) -> &'a mut Device {
+                self.device
+            }
+
+            /// Rename the device, see [`Hub::rename`].
+            pub async fn rename(&mut self, hub: &mut Hub, new_name: &str) -> anyhow::Result<()> {
+                hub.rename(self.device, new_name).await
+            }
+        }
+    };
+}
+
+/// Add the operations for devices that can be turned on and off.
+macro_rules! on_off {
+    ($name:ident) => {
+        impl $name<'_> {
+            /// Whether the device is on.
+            pub fn is_on(&self) -> bool {
+                self.device.is_on().unwrap_or_default()
+            }
+
+            /// Toggle the device on or off, see [`Hub::toggle_on_off`].
+            pub async fn toggle(&mut self, hub: &mut Hub) -> anyhow::Result<()> {
+                hub.toggle_on_off(self.device).await
+            }
+
+            /// Turn the device on or off, see [`Hub::set_on`].
+            pub async fn set_on(&mut self, hub: &mut Hub, is_on: bool) -> anyhow::Result<()> {
+                hub.set_on(self.device, is_on).await
+            }
+
+            /// Turn the device on, see [`Hub::turn_on`].
+            pub async fn turn_on(&mut self, hub: &mut Hub) -> anyhow::Result<()> {
+                hub.turn_on(self.device).await
+            }
+
+            /// Turn the device off, see [`Hub::turn_off`].
+            pub async fn turn_off(&mut self, hub: &mut Hub) -> anyhow::Result<()> {
+                hub.turn_off(self.device).await
+            }
+
+            /// Set the startup behaviour, see [`Hub::set_startup_behaviour`].
+            pub async fn set_startup_behaviour(
+                &mut self,
+                hub: &mut Hub,
+                behaviour: Startup,
+            ) -> anyhow::Result<()> {
+                hub.set_startup_behaviour(self.device, behaviour).await
+            }
+        }
+    };
+}
+
+/// Add the operations for lights that can be dimmed.
+macro_rules! dimmable {
+    ($name:ident) => {
+        impl $name<'_> {
+            /// The current light level between 0 and 100.
+            pub fn light_level(&self) -> u8 {
+                match &*self.device {
+                    Device::Light(light) => light.attributes.light_level.unwrap_or(0),
+                    _ => 0,
+                }
+            }
+
+            /// Set the light level, see [`Hub::set_light_level`].
+            pub async fn set_light_level(&mut self, hub: &mut Hub, level: u8) -> anyhow::Result<()> {
+                hub.set_light_level(self.device, level).await
+            }
+        }
+    };
+}
+
+handle!(
+    /// A light that can be turned on and off.
+    Light
+);
+on_off!(Light);
+
+handle!(
+    /// A light that can be turned on and off and dimmed.
+    DimmableLight
+);
+on_off!(DimmableLight);
+dimmable!(DimmableLight);
+
+handle!(
+    /// A dimmable light with a white spectrum that can change color temperature.
+    TemperatureLight
+);
+on_off!(TemperatureLight);
+dimmable!(TemperatureLight);
+
+handle!(
+    /// A dimmable light that can change hue and saturation.
+    ColorLight
+);
+on_off!(ColorLight);
+dimmable!(ColorLight);
+
+handle!(
+    /// An outlet that can be turned on and off.
+    Outlet
+);
+on_off!(Outlet);
+
+handle!(
+    /// Blinds that can be moved to a target level.
+    Blinds
+);
+
+impl TemperatureLight<'_> {
+    /// Set the color temperature, see [`Hub::set_temperature`].
+    pub async fn set_temperature(&mut self, hub: &mut Hub, temperature: u16) -> anyhow::Result<()> {
+        hub.set_temperature(self.device, temperature).await
+    }
+}
+
+impl ColorLight<'_> {
+    /// Set hue and saturation, see [`Hub::set_hue_saturation`].
+    pub async fn set_hue_saturation(
+        &mut self,
+        hub: &mut Hub,
+        hue: f64,
+        saturation: f64,
+    ) -> anyhow::Result<()> {
+        hub.set_hue_saturation(self.device, hue, saturation).await
+    }
+
+    /// Get a [`TemperatureLight`] handle for the same light if it can also change color
+    /// temperature.
+    pub fn as_temperature_light(&mut self) -> Option<TemperatureLight<'_>> {
+        self.device.as_temperature_light()
+    }
+}
+
+impl Blinds<'_> {
+    /// Fully open the blinds, see [`Hub::open_blinds`].
+    pub async fn open(&mut self, hub: &mut Hub) -> anyhow::Result<()> {
+        hub.open_blinds(self.device).await
+    }
+
+    /// Fully close the blinds, see [`Hub::close_blinds`].
+    pub async fn close(&mut self, hub: &mut Hub) -> anyhow::Result<()> {
+        hub.close_blinds(self.device).await
+    }
+
+    /// Stop the blinds where they are, see [`Hub::stop_blinds`].
+    pub async fn stop(&mut self, hub: &mut Hub) -> anyhow::Result<()> {
+        hub.stop_blinds(self.device).await
+    }
+
+    /// Move the blinds to a target level, see [`Hub::set_target_level`].
+    pub async fn set_target_level(&mut self, hub: &mut Hub, level: u8) -> anyhow::Result<()> {
+        hub.set_target_level(self.device, level).await
+    }
+}
+
+impl Device {
+    /// Get a [`Light`] handle if this is a light that can be turned on and off.
+    pub fn as_light(&mut self) -> Option<Light<'_>> {
+        if !matches!(self, Device::Light(_)) || !self.can_receive(&[Capability::IsOn]) {
+            return None;
+        }
+
+        Some(Light { device: self })
+    }
+
+    /// Get a [`DimmableLight`] handle if this is a light that can be turned on and off and dimmed.
+    pub fn as_dimmable_light(&mut self) -> Option<DimmableLight<'_>> {
+        if !matches!(self, Device::Light(_))
+            || !self.can_receive(&[
+                Capability::IsOn,
+                Capability::LightLevel,
+            ])
+        {
+            return None;
+        }
+
+        Some(DimmableLight { device: self })
+    }
+
+    /// Get a [`TemperatureLight`] handle if this is a dimmable light that can change color
+    /// temperature.
+    pub fn as_temperature_light(&mut self) -> Option<TemperatureLight<'_>> {
+        if !matches!(self, Device::Light(_))
+            || !self.can_receive(&[
+                Capability::IsOn,
+                Capability::LightLevel,
+                Capability::ColorTemperature,
+            ])
+        {
+            return None;
+        }
+
+        Some(TemperatureLight { device: self })
+    }
+
+    /// Get a [`ColorLight`] handle if this is a dimmable light that can change hue and
+    /// saturation.
+    pub fn as_color_light(&mut self) -> Option<ColorLight<'_>> {
+        if !matches!(self, Device::Light(_))
+            || !self.can_receive(&[
+                Capability::IsOn,
+                Capability::LightLevel,
+                Capability::ColorHue,
+                Capability::ColorSaturation,
+            ])
+        {
+            return None;
+        }
+
+        Some(ColorLight { device: self })
+    }
+
+    /// Get an [`Outlet`] handle if this is an outlet that can be turned on and off.
+    pub fn as_outlet(&mut self) -> Option<Outlet<'_>> {
+        if !matches!(self, Device::Outlet(_)) || !self.can_receive(&[Capability::IsOn]) {
+            return None;
+        }
+
+        Some(Outlet { device: self })
+    }
+
+    /// Get a [`Blinds`] handle if these are blinds that can be moved.
+    pub fn as_blinds(&mut self) -> Option<Blinds<'_>> {
+        if !matches!(self, Device::Blinds(_)) || !self.can_receive(&[Capability::BlindsState]) {
+            return None;
+        }
+
+        Some(Blinds { device: self })
+    }
+
+    /// Whether the [`Device`] can receive all of the given [`Capability`]s.
+    pub fn can_receive(&self, capabilities: &[Capability]) -> bool {
+        crate::hub::has_capability(&self.inner().capabilities.can_receive, capabilities)
+    }
+}
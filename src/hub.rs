@@ -6,6 +6,7 @@
 //! `config` feature flag respectively.
 use std::net::Ipv4Addr;
 use std::collections::HashMap;
+use futures::{Stream, StreamExt};
 use reqwest::Client;
 use reqwest::header::{
     HeaderMap,
@@ -16,17 +17,19 @@ use reqwest::header::{
 use url_builder::{url_builder, Part};
 
 use crate::Device;
+use crate::Event;
 use crate::DIRIGERA_API_VERSION;
 use crate::DIRIGERA_PORT;
 use crate::traits::DirigeraExt;
 use crate::config::Config;
 
 /// A [`Hub`] consists of a [`reqwest`] client, and the hub's IP address to communicate with
-/// it.
+/// it. The bearer token is kept to authenticate the event WebSocket.
 #[derive(Debug, Clone)]
 pub struct Hub {
     client: Client,
     ip_address: Ipv4Addr,
+    authorization: HeaderValue,
 }
 
 #[async_trait::async_trait]
@@ -36,16 +39,17 @@ impl DirigeraExt for Hub {
     type Device = Device;
 
     fn new(config: &Self::Config) -> Result<Self, Self::Rejection> {
+        let bearer_token = format!("Bearer {}", config.token);
+        let mut authorization = HeaderValue::from_str(&bearer_token)?;
+        authorization.set_sensitive(true);
+
         // base_url PR https://github.com/seanmonstar/reqwest/pull/1620
         let client = Client::builder()
             .danger_accept_invalid_certs(true)
             .user_agent(crate::user_agent())
             .default_headers({
                 let mut headers = HeaderMap::new();
-                let bearer_token = format!("Bearer {}", config.token);
-                let mut auth_value = HeaderValue::from_str(&bearer_token)?;
-                auth_value.set_sensitive(true);
-                headers.insert(AUTHORIZATION, auth_value);
+                headers.insert(AUTHORIZATION, authorization.clone());
                 let content_type = HeaderValue::from_static("application/json");
                 headers.insert(CONTENT_TYPE, content_type);
 
@@ -56,29 +60,20 @@ impl DirigeraExt for Hub {
         Ok(Self {
             client,
             ip_address: config.ip_address,
+            authorization,
         })
     }
 
-    /// List all devices that is known for the [`Hub`]. This will return an exhaustive list of
-    /// [`Device`](crate::Device)s.
+    /// List all devices that is known for the [`Hub`]. A [`Device`](crate::Device) that can't be
+    /// parsed is skipped so a single odd device doesn't hide the others, use
+    /// [`Hub::list_lenient`] to see why it failed.
     async fn list(&self) -> Result<Vec<Self::Device>, Self::Rejection> {
-        self.client
-            .get({
-                url_builder! {
-                    Part::Scheme("https");
-                    Part::HostIpv4(self.ip_address);
-                    Part::Port(DIRIGERA_PORT);
-                    Part::PathSlice(&[
-                        DIRIGERA_API_VERSION,
-                        "devices",
-                    ])
-                }?
-            })
-            .send()
+        Ok(self
+            .list_lenient()
             .await?
-            .json::<Vec<Device>>()
-            .await
-            .map_err(Self::Rejection::BuildError)
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect())
     }
 
     /// Get a single [`Device`](crate::Device) based on its id.
@@ -106,6 +101,126 @@ impl DirigeraExt for Hub {
 }
 
 impl Hub {
+    /// List all devices that is known for the [`Hub`] and parse each one separately. Unlike
+    /// [`DirigeraExt::list`](crate::traits::DirigeraExt::list), which skips them, a
+    /// [`Device`](crate::Device) that can't be parsed is returned as a
+    /// [`DeviceParseError`](crate::DeviceParseError) in its place.
+    pub async fn list_lenient(
+        &self,
+    ) -> Result<Vec<Result<Device, crate::DeviceParseError>>, crate::Error> {
+        let devices = self
+            .client
+            .get({
+                make_url(self.ip_address, "/devices")?
+            })
+            .send()
+            .await?
+            .json::<Vec<serde_json::Value>>()
+            .await?;
+
+        Ok(devices
+            .into_iter()
+            .map(|raw| {
+                serde_json::from_value(raw.clone()).map_err(|source| {
+                    crate::DeviceParseError {
+                        id: raw.get("id").and_then(|id| id.as_str()).map(String::from),
+                        raw,
+                        source,
+                    }
+                })
+            })
+            .collect())
+    }
+
+    /// Subscribe to [`Event`](crate::Event)s pushed by the [`Hub`]. This opens the hub's
+    /// WebSocket with the same token and TLS settings as the rest of the API. The stream ends
+    /// when the hub closes the connection.
+    pub async fn events(
+        &self,
+    ) -> Result<impl Stream<Item = Result<Event, crate::Error>>, crate::Error> {
+        let socket = self.connect_events().await?;
+
+        Ok(socket.filter_map(|message| async move {
+            crate::events::decode(message)
+        }))
+    }
+
+    /// Subscribe to [`Event`](crate::Event)s pushed by the [`Hub`] and keep the subscription
+    /// alive. Whenever the connection is lost an
+    /// [`Event::Disconnected`](crate::Event::Disconnected) is emitted and a new connection is
+    /// attempted with exponential [`Backoff`](crate::events::Backoff). Once connected again an
+    /// [`Event::Reconnected`](crate::Event::Reconnected) with the full list of
+    /// [`Device`](crate::Device)s is emitted so anything missed while offline can be reconciled.
+    /// A quiet connection is pinged and one that stays silent is treated as lost, so a half-open
+    /// connection doesn't stall the stream. Only messages that fail to decode are yielded as
+    /// errors, the stream never ends.
+    pub fn subscribe(
+        &self,
+        backoff: crate::events::Backoff,
+    ) -> impl Stream<Item = Result<Event, crate::Error>> {
+        crate::events::Subscription::new(self.clone(), backoff).into_stream()
+    }
+
+    /// Poll the [`Hub`] for [`Device`](crate::Device)s every `interval` and report what changed
+    /// since the previous poll as [`WatchEvent`](crate::watch::WatchEvent)s. This is a fallback
+    /// for when the event WebSocket can't be used. The first poll is only used as a baseline and
+    /// a failed poll is yielded as an error without stopping the stream. Changes of `last_seen`
+    /// aren't reported since it changes on nearly every poll.
+    pub fn watch(
+        &self,
+        interval: std::time::Duration,
+    ) -> impl Stream<Item = Result<crate::watch::WatchEvent, crate::Error>> {
+        crate::watch::Watcher::new(self.clone(), interval).into_stream()
+    }
+
+    /// Start an [`Update`](crate::update::Update) to change several attributes of the
+    /// [`Device`](crate::Device) in a single request. The [`Device`](crate::Device) is updated
+    /// once the change has been accepted by the [`Hub`].
+    pub fn update<'a>(&'a mut self, device: &'a mut Device) -> crate::update::Update<'a> {
+        crate::update::Update::new(self, device)
+    }
+
+    /// Send a single PATCH with the given attributes for the [`Device`](crate::Device) with the
+    /// given id, optionally with a transition time for the hub to apply them over.
+    pub(crate) async fn patch_attributes(
+        &self,
+        id: &str,
+        attributes: &serde_json::Map<String, serde_json::Value>,
+        transition: Option<std::time::Duration>,
+    ) -> Result<(), crate::Error> {
+        self.patch(&format!("/devices/{id}"), attributes, transition).await
+    }
+
+    /// Send a single PATCH with the given attributes to the given path.
+    async fn patch(
+        &self,
+        path: &str,
+        attributes: &serde_json::Map<String, serde_json::Value>,
+        transition: Option<std::time::Duration>,
+    ) -> Result<(), crate::Error> {
+        let mut body = serde_json::json!({ "attributes": attributes });
+        if let Some(transition) = transition {
+            body["transitionTime"] = serde_json::Value::from(transition.as_millis() as u64);
+        }
+
+        let body = serde_json::to_string(&vec![body])?;
+
+        self.client
+            .patch({
+                make_url(self.ip_address, path)?
+            })
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn connect_events(&self) -> Result<crate::events::Socket, crate::Error> {
+        crate::events::connect(self.ip_address, &self.authorization).await
+    }
+
     /// Rename a [`Device`](crate::Device). The function takes a mutable reference to the
     /// [`Device`](crate::Device) because on successful renaming the passed
     /// [`Device`](crate::Device) will be updated with the new name.
@@ -114,7 +229,7 @@ impl Hub {
         device: &mut crate::device::Device,
         new_name: &str,
     ) -> anyhow::Result<()> {
-        let inner = device.inner_mut();
+        let inner = device.inner();
 
         if !has_capability(
             inner.capabilities.can_receive.as_ref(),
@@ -138,9 +253,68 @@ impl Hub {
             .body(body)
             .send()
             .await
+            .and_then(reqwest::Response::error_for_status)
             .map_err(|err| anyhow::anyhow!(err))?;
 
-        inner.attributes.custom_name = new_name.to_string();
+        device.common_attributes_mut().custom_name = new_name.to_string();
+
+        Ok(())
+    }
+
+    /// Hide or unhide a [`Device`](crate::Device). Hidden devices aren't shown in the IKEA app.
+    /// The function takes a mutable reference to the [`Device`](crate::Device) because on
+    /// successful change the passed [`Device`](crate::Device) will be updated.
+    pub async fn set_hidden(
+        &mut self,
+        device: &mut crate::device::Device,
+        is_hidden: bool,
+    ) -> anyhow::Result<()> {
+        let inner = device.inner();
+
+        let body = serde_json::to_string(&vec![serde_json::json!({ "isHidden": is_hidden })])?;
+
+        self.client
+            .patch({
+                make_url(self.ip_address, &format!("/devices/{}", inner.id))?
+            })
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        device.inner_mut().is_hidden = Some(is_hidden);
+
+        Ok(())
+    }
+
+    /// Remove a [`Device`](crate::Device) from the [`Hub`]. The device has to be paired again to
+    /// be used. To guard against accidents the removal must either be confirmed with the id of
+    /// the [`Device`](crate::Device) or forced with [`Removal`](crate::device::Removal). The
+    /// gateway itself can't be removed.
+    pub async fn remove_device(
+        &mut self,
+        device: &crate::device::Device,
+        removal: crate::device::Removal<'_>,
+    ) -> anyhow::Result<()> {
+        let inner = device.inner();
+
+        if let crate::device::Removal::Confirm(id) = removal {
+            if id != inner.id {
+                anyhow::bail!("confirmation {id} does not match device {}", inner.id);
+            }
+        }
+
+        if matches!(device, Device::Gateway(_)) {
+            anyhow::bail!("the gateway cannot be removed");
+        }
+
+        self.client
+            .delete({
+                make_url(self.ip_address, &format!("/devices/{}", inner.id))?
+            })
+            .send()
+            .await?
+            .error_for_status()?;
 
         Ok(())
     }
@@ -154,7 +328,7 @@ impl Hub {
         &mut self,
         device: &mut crate::device::Device,
     ) -> anyhow::Result<()> {
-        let inner = device.inner_mut();
+        let inner = device.inner();
 
         if !has_capability(
             inner.capabilities.can_receive.as_ref(),
@@ -163,11 +337,12 @@ impl Hub {
             anyhow::bail!("device cannot be toggled");
         }
 
+        let is_on = !device
+            .is_on()
+            .ok_or_else(|| anyhow::anyhow!("device has no on or off state"))?;
+
         let mut attributes = HashMap::new();
-        inner
-            .attributes
-            .is_on
-            .map(|x| attributes.insert("isOn", !x));
+        attributes.insert("isOn", is_on);
 
         let mut body = HashMap::new();
         body.insert("attributes", attributes);
@@ -181,9 +356,108 @@ impl Hub {
             .body(body)
             .send()
             .await
+            .and_then(reqwest::Response::error_for_status)
             .map_err(|err| anyhow::anyhow!(err))?;
 
-        inner.attributes.is_on = inner.attributes.is_on.map(|x| !x);
+        match device {
+            Device::Light(light) => light.attributes.is_on = is_on,
+            Device::Outlet(outlet) => outlet.attributes.is_on = is_on,
+            Device::Controller(controller) => controller.attributes.is_on = Some(is_on),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Toggle a [`Device`](crate::Device) on and off like [`Hub::toggle_on_off`], but read the
+    /// current state from the [`Hub`] first instead of trusting the passed
+    /// [`Device`](crate::Device), which may be stale if something else changed it.
+    pub async fn toggle_on_off_refreshed(
+        &mut self,
+        device: &mut crate::device::Device,
+    ) -> anyhow::Result<()> {
+        self.refresh(device).await?;
+        self.toggle_on_off(device).await
+    }
+
+    /// Turn a [`Device`](crate::Device) on or off. Unlike [`Hub::toggle_on_off`] the state is
+    /// sent as is, so calling this more than once has the same effect as calling it once.
+    /// Requires the [`Device`](crate::Device) to support
+    /// [`Capability::IsOn`](crate::device::Capability::IsOn) as a receivable capability. The
+    /// function takes a mutable reference to the [`Device`](crate::Device) because on successful
+    /// change the passed [`Device`](crate::Device) will be updated with the new state.
+    pub async fn set_on(
+        &mut self,
+        device: &mut crate::device::Device,
+        is_on: bool,
+    ) -> anyhow::Result<()> {
+        self.update(device).on(is_on).send().await
+    }
+
+    /// Turn a [`Device`](crate::Device) on, see [`Hub::set_on`].
+    pub async fn turn_on(&mut self, device: &mut crate::device::Device) -> anyhow::Result<()> {
+        self.set_on(device, true).await
+    }
+
+    /// Turn a [`Device`](crate::Device) off, see [`Hub::set_on`].
+    pub async fn turn_off(&mut self, device: &mut crate::device::Device) -> anyhow::Result<()> {
+        self.set_on(device, false).await
+    }
+
+    /// Replace the passed [`Device`](crate::Device) with its current state read from the
+    /// [`Hub`].
+    pub async fn refresh(&mut self, device: &mut crate::device::Device) -> anyhow::Result<()> {
+        *device = self.get(&device.inner().id).await?;
+
+        Ok(())
+    }
+
+    /// Make a [`Device`](crate::Device) identify itself, f.ex. by blinking, to tell it apart from
+    /// identical ones. Devices that support it identify themselves. Lights that don't are flashed
+    /// on and off a few times and then restored to their prior on and off state and light level.
+    /// Anything else, f.ex. an outlet that could power-cycle whatever is plugged in, can't be
+    /// identified.
+    pub async fn identify(&mut self, device: &crate::device::Device) -> anyhow::Result<()> {
+        let id = &device.inner().id;
+
+        match Identify::for_device(device)? {
+            Identify::Native => {
+                self.client
+                    .put({
+                        make_url(self.ip_address, &format!("/devices/{}/identify", id))?
+                    })
+                    .send()
+                    .await?
+                    .error_for_status()?;
+
+                Ok(())
+            }
+            Identify::Flash(restore) => {
+                let is_on = device.is_on().unwrap_or_default();
+                let flashed = self.flash(id, !is_on).await;
+
+                // Restore the prior state even if flashing failed half way.
+                self.patch_attributes(id, &restore, None).await?;
+
+                flashed
+            }
+        }
+    }
+
+    /// Flash a [`Device`](crate::Device) by switching it on and off, starting with `is_on`.
+    async fn flash(&self, id: &str, mut is_on: bool) -> anyhow::Result<()> {
+        const FLASHES: usize = 3;
+        const PERIOD: std::time::Duration = std::time::Duration::from_millis(500);
+
+        for _ in 0..FLASHES * 2 {
+            let mut attributes = serde_json::Map::new();
+            attributes.insert("isOn".to_string(), is_on.into());
+
+            self.patch_attributes(id, &attributes, None).await?;
+            tokio::time::sleep(PERIOD).await;
+
+            is_on = !is_on;
+        }
 
         Ok(())
     }
@@ -198,10 +472,12 @@ impl Hub {
         device: &mut crate::device::Device,
         level: u8,
     ) -> anyhow::Result<()> {
-        let inner = device.inner_mut();
+        let Device::Light(light) = device else {
+            anyhow::bail!("device cannot set light level");
+        };
 
         if !has_capability(
-            inner.capabilities.can_receive.as_ref(),
+            light.capabilities.can_receive.as_ref(),
             &[crate::device::Capability::LightLevel],
         ) {
             anyhow::bail!("device cannot set light level");
@@ -221,14 +497,15 @@ impl Hub {
 
         self.client
             .patch({
-                make_url(self.ip_address, &format!("/devices/{}", inner.id))?
+                make_url(self.ip_address, &format!("/devices/{}", light.id))?
             })
             .body(body)
             .send()
             .await
+            .and_then(reqwest::Response::error_for_status)
             .map_err(|err| anyhow::anyhow!(err))?;
 
-        inner.attributes.light_level = Some(level);
+        light.attributes.light_level = Some(level);
 
         Ok(())
     }
@@ -244,20 +521,22 @@ impl Hub {
         device: &mut crate::device::Device,
         temperature: u16,
     ) -> anyhow::Result<()> {
-        let inner = device.inner_mut();
+        let Device::Light(light) = device else {
+            anyhow::bail!("device cannot set color temperature");
+        };
 
         if !has_capability(
-            inner.capabilities.can_receive.as_ref(),
+            light.capabilities.can_receive.as_ref(),
             &[crate::device::Capability::ColorTemperature],
         ) {
             anyhow::bail!("device cannot set color temperature");
         }
 
-        let min = inner
+        let min = light
             .attributes
             .color_temperature_min
             .ok_or_else(|| anyhow::anyhow!("device has no min temperature value"))?;
-        let max = inner
+        let max = light
             .attributes
             .color_temperature_max
             .ok_or_else(|| anyhow::anyhow!("device has no max temperature value"))?;
@@ -276,14 +555,16 @@ impl Hub {
 
         self.client
             .patch({
-                make_url(self.ip_address, &format!("/devices/{}", inner.id))?
+                make_url(self.ip_address, &format!("/devices/{}", light.id))?
             })
             .body(body)
             .send()
             .await
+            .and_then(reqwest::Response::error_for_status)
             .map_err(|err| anyhow::anyhow!(err))?;
 
-        inner.attributes.color_temperature = Some(temperature);
+        light.attributes.color_temperature = Some(temperature);
+        light.attributes.color_mode = Some(crate::device::ColorMode::Temperature);
 
         Ok(())
     }
@@ -301,10 +582,12 @@ impl Hub {
         hue: f64,
         saturation: f64,
     ) -> anyhow::Result<()> {
-        let inner = device.inner_mut();
+        let Device::Light(light) = device else {
+            anyhow::bail!("device cannot be change for hue and saturation");
+        };
 
         if !has_capability(
-            inner.capabilities.can_receive.as_ref(),
+            light.capabilities.can_receive.as_ref(),
             &[
                 crate::device::Capability::ColorHue,
                 crate::device::Capability::ColorSaturation,
@@ -332,19 +615,111 @@ impl Hub {
 
         self.client
             .patch({
-                make_url(self.ip_address, &format!("/devices/{}", inner.id))?
+                make_url(self.ip_address, &format!("/devices/{}", light.id))?
             })
             .body(body)
             .send()
             .await
+            .and_then(reqwest::Response::error_for_status)
             .map_err(|err| anyhow::anyhow!(err))?;
 
-        inner.attributes.color_hue = Some(hue);
-        inner.attributes.color_saturation = Some(hue);
+        light.attributes.color_hue = Some(hue);
+        light.attributes.color_saturation = Some(saturation);
+        light.attributes.color_mode = Some(crate::device::ColorMode::Color);
 
         Ok(())
     }
 
+    /// Set a [`Color`](crate::Color) on the [`Device`](crate::Device). A color temperature is
+    /// sent as one if the [`Device`](crate::Device) supports
+    /// [`Capability::ColorTemperature`](crate::device::Capability::ColorTemperature), any other
+    /// color is sent as hue and saturation if supported. A white spectrum light gets the
+    /// correlated color temperature of the color instead. Color temperatures are clamped to the
+    /// range the [`Device`](crate::Device) supports. The brightness of the color isn't applied, use
+    /// [`Hub::set_light_level`] for that.
+    pub async fn set_color(
+        &mut self,
+        device: &mut crate::device::Device,
+        color: crate::Color,
+    ) -> anyhow::Result<()> {
+        let Device::Light(light) = device else {
+            anyhow::bail!("device cannot set color");
+        };
+
+        let can_receive = light.capabilities.can_receive.as_ref();
+        let hue_saturation = has_capability(
+            can_receive,
+            &[
+                crate::device::Capability::ColorHue,
+                crate::device::Capability::ColorSaturation,
+            ],
+        );
+        let temperature =
+            has_capability(can_receive, &[crate::device::Capability::ColorTemperature]);
+
+        if hue_saturation && !(temperature && matches!(color, crate::Color::Kelvin(_))) {
+            let hsv = color.to_hsv();
+            return self.set_hue_saturation(device, hsv.hue, hsv.saturation).await;
+        }
+
+        if !temperature {
+            anyhow::bail!("device cannot set color");
+        }
+
+        let kelvin = light.attributes.clamp_temperature(color.to_kelvin());
+
+        self.set_temperature(device, kelvin).await
+    }
+
+    /// Like [`Hub::set_light_level`] but lets the hub transition to the new light level over the
+    /// given duration. For devices that ignore the transition time use
+    /// [`Update::fade`](crate::update::Update::fade).
+    pub async fn set_light_level_with_transition(
+        &mut self,
+        device: &mut crate::device::Device,
+        level: u8,
+        transition: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        self.update(device)
+            .light_level(level)
+            .transition(transition)
+            .send()
+            .await
+    }
+
+    /// Like [`Hub::set_temperature`] but lets the hub transition to the new color temperature
+    /// over the given duration. For devices that ignore the transition time use
+    /// [`Update::fade`](crate::update::Update::fade).
+    pub async fn set_temperature_with_transition(
+        &mut self,
+        device: &mut crate::device::Device,
+        temperature: u16,
+        transition: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        self.update(device)
+            .color_temperature(temperature)
+            .transition(transition)
+            .send()
+            .await
+    }
+
+    /// Like [`Hub::set_hue_saturation`] but lets the hub transition to the new hue and
+    /// saturation over the given duration. For devices that ignore the transition time use
+    /// [`Update::fade`](crate::update::Update::fade).
+    pub async fn set_hue_saturation_with_transition(
+        &mut self,
+        device: &mut crate::device::Device,
+        hue: f64,
+        saturation: f64,
+        transition: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        self.update(device)
+            .hue_saturation(hue, saturation)
+            .transition(transition)
+            .send()
+            .await
+    }
+
     /// Set startup behaviour on the [`Device`](crate::Device). The function takes a mutable
     /// reference to the [`Device`](crate::Device) because on successful change the passed
     /// [`Device`](crate::Device) will be updated with the new startup behaviour.
@@ -353,7 +728,7 @@ impl Hub {
         device: &mut crate::device::Device,
         behaviour: crate::device::Startup,
     ) -> anyhow::Result<()> {
-        let inner = device.inner_mut();
+        let inner = device.inner();
 
         let mut attributes = HashMap::new();
         attributes.insert("startupOnOff", &behaviour);
@@ -370,9 +745,14 @@ impl Hub {
             .body(body)
             .send()
             .await
+            .and_then(reqwest::Response::error_for_status)
             .map_err(|err| anyhow::anyhow!(err))?;
 
-        inner.attributes.startup_on_off = Some(behaviour);
+        match device {
+            Device::Light(light) => light.attributes.startup_on_off = Some(behaviour),
+            Device::Outlet(outlet) => outlet.attributes.startup_on_off = Some(behaviour),
+            _ => {}
+        }
 
         Ok(())
     }
@@ -387,10 +767,12 @@ impl Hub {
         device: &mut crate::device::Device,
         level: u8,
     ) -> anyhow::Result<()> {
-        let inner = device.inner_mut();
+        let Device::Blinds(blinds) = device else {
+            anyhow::bail!("device cannot be change for blind state");
+        };
 
         if !has_capability(
-            inner.capabilities.can_receive.as_ref(),
+            blinds.capabilities.can_receive.as_ref(),
             &[crate::device::Capability::BlindsState],
         ) {
             anyhow::bail!("device cannot be change for blind state");
@@ -410,18 +792,103 @@ impl Hub {
 
         self.client
             .patch({
-                make_url(self.ip_address, &format!("/devices/{}", inner.id))?
+                make_url(self.ip_address, &format!("/devices/{}", blinds.id))?
             })
             .body(body)
             .send()
             .await
+            .and_then(reqwest::Response::error_for_status)
             .map_err(|err| anyhow::anyhow!(err))?;
 
-        inner.attributes.blinds_target_level = Some(level);
+        blinds.attributes.blinds_target_level = level;
+
+        Ok(())
+    }
+
+    /// Fully open the [`Device::Blinds`](crate::Device::Blinds), see [`Hub::set_target_level`].
+    pub async fn open_blinds(&mut self, device: &mut crate::device::Device) -> anyhow::Result<()> {
+        self.set_target_level(device, 0).await
+    }
+
+    /// Fully close the [`Device::Blinds`](crate::Device::Blinds), see
+    /// [`Hub::set_target_level`].
+    pub async fn close_blinds(&mut self, device: &mut crate::device::Device) -> anyhow::Result<()> {
+        self.set_target_level(device, 100).await
+    }
+
+    /// Stop the [`Device::Blinds`](crate::Device::Blinds) where they are. Requires the
+    /// [`Device`](crate::Device) to support
+    /// [`Capability::BlindsState`](crate::device::Capability::BlindsState) as a receivable
+    /// capability. The function takes a mutable reference to the [`Device`](crate::Device)
+    /// because on successful change the passed [`Device`](crate::Device) will be updated with
+    /// the new state.
+    pub async fn stop_blinds(&mut self, device: &mut crate::device::Device) -> anyhow::Result<()> {
+        let Device::Blinds(blinds) = device else {
+            anyhow::bail!("device cannot be change for blind state");
+        };
+
+        if !has_capability(
+            blinds.capabilities.can_receive.as_ref(),
+            &[crate::device::Capability::BlindsState],
+        ) {
+            anyhow::bail!("device cannot be change for blind state");
+        }
+
+        let mut attributes = serde_json::Map::new();
+        attributes.insert(
+            "blindsState".to_string(),
+            serde_json::to_value(crate::device::BlindsState::Stopped)?,
+        );
+
+        self.patch_attributes(&blinds.id, &attributes, None).await?;
+
+        blinds.attributes.blinds_state = Some(crate::device::BlindsState::Stopped);
 
         Ok(())
     }
 
+    /// Wait until the [`Device::Blinds`](crate::Device::Blinds) are within `tolerance` of their
+    /// target level. The [`Device`](crate::Device) is read from the [`Hub`] every half second
+    /// and the passed [`Device`](crate::Device) is kept up to date with the latest read. Fails
+    /// if the position isn't reached before `timeout`.
+    pub async fn wait_until_position(
+        &mut self,
+        device: &mut crate::device::Device,
+        tolerance: u8,
+        timeout: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        const POLL: std::time::Duration = std::time::Duration::from_millis(500);
+
+        if !matches!(device, Device::Blinds(_)) {
+            anyhow::bail!("device is not blinds");
+        }
+
+        let wait = async {
+            let mut interval = tokio::time::interval(POLL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                self.refresh(device).await?;
+
+                let Device::Blinds(blinds) = &*device else {
+                    anyhow::bail!("device is not blinds");
+                };
+
+                let current = blinds.attributes.blinds_current_level;
+                let target = blinds.attributes.blinds_target_level;
+
+                if current.abs_diff(target) <= tolerance {
+                    return Ok(());
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| anyhow::anyhow!("blinds did not reach their target level in time"))?
+    }
+
     /// List all scenes that is known for the [`Hub`]. This will return an exhaustive list of
     /// [`Scene`](crate::Scene)s.
     pub async fn scenes(&mut self) -> anyhow::Result<Vec<crate::Scene>> {
@@ -449,42 +916,771 @@ impl Hub {
             .map_err(|err| anyhow::anyhow!(err))
     }
 
-    /*/// Trigger a [`Scene`](crate::Scene) now. Will work independent of a scheduled scene or not.
-    pub async fn trigger_scene(&mut self, scene: &crate::scene::Scene) -> anyhow::Result<()> {
-        let inner = scene.inner();
+    /// List all rooms that is known for the [`Hub`]. This will return an exhaustive list of
+    /// [`Room`](crate::device::Room)s.
+    pub async fn rooms(&mut self) -> anyhow::Result<Vec<crate::device::Room>> {
+        self.client
+            .get({
+                make_url(self.ip_address, "/rooms")?
+            })
+            .send()
+            .await?
+            .json::<Vec<crate::device::Room>>()
+            .await
+            .map_err(|err| anyhow::anyhow!(err))
+    }
 
+    /// Get a single [`Room`](crate::device::Room) based on its id.
+    pub async fn room(&mut self, id: &str) -> anyhow::Result<crate::device::Room> {
         self.client
-            .call(self.create_request(
-                http::Method::POST,
-                format!("/scenes/{}/trigger", inner.id).as_str(),
-                Some(hyper::Body::empty()),
-            )?)
-            .await?;
+            .get({
+                make_url(self.ip_address, &format!("/rooms/{}", id))?
+            })
+            .send()
+            .await?
+            .json::<crate::device::Room>()
+            .await
+            .map_err(|err| anyhow::anyhow!(err))
+    }
 
-        Ok(())
-    }*/
+    /// Create a new [`Room`](crate::device::Room). Icon and color are the identifiers used by the
+    /// IKEA app, f.ex. `rooms_sofa` and `ikea_green_no_65`.
+    pub async fn create_room(
+        &mut self,
+        name: &str,
+        icon: &str,
+        color: &str,
+    ) -> anyhow::Result<crate::device::Room> {
+        #[derive(serde::Deserialize)]
+        struct Created {
+            id: String,
+        }
 
-    /*/// Undo scene will revert the changes set by the [`Scene`](crate::Scene).
-    pub async fn undo_scene(&mut self, scene: &crate::scene::Scene) -> anyhow::Result<()> {
-        let inner = scene.inner();
+        let body = serde_json::to_string(&serde_json::json!({
+            "name": name,
+            "icon": icon,
+            "color": color,
+        }))?;
 
-        self.client
-            .call(self.create_request(
-                http::Method::POST,
-                format!("/scenes/{}/undo", inner.id).as_str(),
-                Some(hyper::Body::empty()),
-            )?)
+        let created = self
+            .client
+            .post({
+                make_url(self.ip_address, "/rooms")?
+            })
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Created>()
             .await?;
 
-        Ok(())
-    }*/
-}
+        Ok(crate::device::Room {
+            id: created.id,
+            name: name.to_string(),
+            color: color.to_string(),
+            icon: icon.to_string(),
+            extra: Default::default(),
+        })
+    }
 
-fn make_url(host: Ipv4Addr, path: &str) -> Result<url::Url, crate::Error> {
-    use Part::*;
+    /// Rename a [`Room`](crate::device::Room). The function takes a mutable reference to the
+    /// [`Room`](crate::device::Room) because on successful renaming the passed
+    /// [`Room`](crate::device::Room) will be updated with the new name.
+    pub async fn rename_room(
+        &mut self,
+        room: &mut crate::device::Room,
+        new_name: &str,
+    ) -> anyhow::Result<()> {
+        let body = serde_json::to_string(&serde_json::json!({ "name": new_name }))?;
 
-    url_builder! {
-        Scheme("https");
+        self.client
+            .patch({
+                make_url(self.ip_address, &format!("/rooms/{}", room.id))?
+            })
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        room.name = new_name.to_string();
+
+        Ok(())
+    }
+
+    /// Delete a [`Room`](crate::device::Room). [`Device`](crate::Device)s in the room are kept
+    /// but no longer belong to any room.
+    pub async fn delete_room(&mut self, room: &crate::device::Room) -> anyhow::Result<()> {
+        self.client
+            .delete({
+                make_url(self.ip_address, &format!("/rooms/{}", room.id))?
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Move a [`Device`](crate::Device) to a [`Room`](crate::device::Room). The function takes a
+    /// mutable reference to the [`Device`](crate::Device) because on successful move the passed
+    /// [`Device`](crate::Device) will be updated with the new room.
+    pub async fn move_to_room(
+        &mut self,
+        device: &mut crate::device::Device,
+        room: &crate::device::Room,
+    ) -> anyhow::Result<()> {
+        let body = serde_json::to_string(&serde_json::json!({
+            "deviceIds": [device.inner().id],
+        }))?;
+
+        self.client
+            .patch({
+                make_url(self.ip_address, &format!("/rooms/{}/move-devices", room.id))?
+            })
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        device.inner_mut().room = Some(room.clone());
+
+        Ok(())
+    }
+
+    /// Turn every [`Device`](crate::Device) in the [`Room`](crate::device::Room) with the given
+    /// id on or off, see [`Hub::set_on`]. Devices that can't be turned on and off are skipped.
+    /// Returns the outcome for every [`Device`](crate::Device).
+    pub async fn room_set_on(
+        &mut self,
+        room_id: &str,
+        is_on: bool,
+    ) -> anyhow::Result<crate::group::GroupReport> {
+        self.room_command(
+            room_id,
+            |device| device.can_receive(&[crate::device::Capability::IsOn]),
+            |mut hub, mut device| async move {
+                hub.set_on(&mut device, is_on).await?;
+                Ok(device)
+            },
+        )
+        .await
+    }
+
+    /// Set the light level of every light in the [`Room`](crate::device::Room) with the given id,
+    /// see [`Hub::set_light_level`]. Lights that can't be dimmed are skipped. Returns the outcome
+    /// for every [`Device`](crate::Device).
+    pub async fn room_set_light_level(
+        &mut self,
+        room_id: &str,
+        level: u8,
+    ) -> anyhow::Result<crate::group::GroupReport> {
+        self.room_command(
+            room_id,
+            |device| device.can_receive(&[crate::device::Capability::LightLevel]),
+            |mut hub, mut device| async move {
+                hub.set_light_level(&mut device, level).await?;
+                Ok(device)
+            },
+        )
+        .await
+    }
+
+    /// Set the color temperature in Kelvin of every light in the [`Room`](crate::device::Room)
+    /// with the given id that supports it. The temperature is clamped to what each light
+    /// supports, see [`Hub::set_color`]. Returns the outcome for every [`Device`](crate::Device).
+    pub async fn room_set_temperature(
+        &mut self,
+        room_id: &str,
+        temperature: u16,
+    ) -> anyhow::Result<crate::group::GroupReport> {
+        self.room_command(
+            room_id,
+            |device| device.can_receive(&[crate::device::Capability::ColorTemperature]),
+            |mut hub, mut device| async move {
+                hub.set_color(&mut device, crate::Color::kelvin(temperature)).await?;
+                Ok(device)
+            },
+        )
+        .await
+    }
+
+    /// Set a [`Color`](crate::Color) on every light in the [`Room`](crate::device::Room) with the
+    /// given id that supports either color temperature or hue and saturation, see
+    /// [`Hub::set_color`]. Returns the outcome for every [`Device`](crate::Device).
+    pub async fn room_set_color(
+        &mut self,
+        room_id: &str,
+        color: crate::Color,
+    ) -> anyhow::Result<crate::group::GroupReport> {
+        self.room_command(
+            room_id,
+            |device| {
+                device.can_receive(&[crate::device::Capability::ColorTemperature])
+                    || device.can_receive(&[
+                        crate::device::Capability::ColorHue,
+                        crate::device::Capability::ColorSaturation,
+                    ])
+            },
+            |mut hub, mut device| async move {
+                hub.set_color(&mut device, color).await?;
+                Ok(device)
+            },
+        )
+        .await
+    }
+
+    /// Get the aggregated [`RoomState`](crate::room::RoomState) of all
+    /// [`Device`](crate::Device)s in the [`Room`](crate::device::Room) with the given id.
+    pub async fn room_state(&mut self, room_id: &str) -> anyhow::Result<crate::room::RoomState> {
+        let devices = self.list().await?;
+
+        Ok(crate::room::RoomState::from_devices(room_id, &devices))
+    }
+
+    /// Run `command` for every [`Device`](crate::Device) in the room with the given id that
+    /// matches `filter`, as a [`Group`](crate::group::Group) of the room. The outcome of every
+    /// command is in the returned [`GroupReport`](crate::group::GroupReport).
+    async fn room_command<F, C, Fut>(
+        &self,
+        room_id: &str,
+        filter: F,
+        command: C,
+    ) -> anyhow::Result<crate::group::GroupReport>
+    where
+        F: Fn(&crate::Device) -> bool + Send + Sync + 'static,
+        C: Fn(Hub, crate::Device) -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<crate::Device>>,
+    {
+        let room_id = room_id.to_string();
+        let room = crate::group::Group::new()
+            .filter(move |device| device.is_in_room(&room_id) && filter(device));
+
+        Ok(room.run(self, &[], command).await?)
+    }
+
+    /// Ask the [`Hub`] to check for firmware updates for all [`Device`](crate::Device)s. The
+    /// result is reported through the [`OtaState`](crate::ota::OtaState) and
+    /// [`OtaStatus`](crate::ota::OtaStatus) of each [`Device`](crate::Device).
+    pub async fn check_for_updates(&mut self) -> anyhow::Result<()> {
+        self.client
+            .put({
+                make_url(self.ip_address, "/hub/ota/check")?
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Set the [`OtaPolicy`](crate::ota::OtaPolicy) of a [`Device`](crate::Device). The function
+    /// takes a mutable reference to the [`Device`](crate::Device) because on successful change the
+    /// passed [`Device`](crate::Device) will be updated with the new policy.
+    pub async fn set_ota_policy(
+        &mut self,
+        device: &mut crate::device::Device,
+        policy: crate::ota::OtaPolicy,
+    ) -> anyhow::Result<()> {
+        let mut attributes = serde_json::Map::new();
+        attributes.insert("otaPolicy".to_string(), serde_json::to_value(&policy)?);
+
+        self.patch_attributes(&device.inner().id, &attributes, None).await?;
+
+        device.common_attributes_mut().ota_policy = policy;
+
+        Ok(())
+    }
+
+    /// Set the window in which firmware updates may be installed on a
+    /// [`Device`](crate::Device). The window may span midnight, f.ex. from 23:00 to 04:00. The
+    /// function takes a mutable reference to the [`Device`](crate::Device) because on successful
+    /// change the passed [`Device`](crate::Device) will be updated with the new window.
+    pub async fn set_ota_schedule(
+        &mut self,
+        device: &mut crate::device::Device,
+        start: crate::ota::TimeOfDay,
+        end: crate::ota::TimeOfDay,
+    ) -> anyhow::Result<()> {
+        let mut attributes = serde_json::Map::new();
+        attributes.insert("otaScheduleStart".to_string(), serde_json::to_value(start)?);
+        attributes.insert("otaScheduleEnd".to_string(), serde_json::to_value(end)?);
+
+        self.patch_attributes(&device.inner().id, &attributes, None).await?;
+
+        let common = device.common_attributes_mut();
+        common.ota_schedule_start = Some(start.into());
+        common.ota_schedule_end = Some(end.into());
+
+        Ok(())
+    }
+
+    /// Follow a firmware update of a [`Device`](crate::Device). Every change of its
+    /// [`OtaState`](crate::ota::OtaState), [`OtaStatus`](crate::ota::OtaStatus) or progress is
+    /// yielded as an [`OtaProgress`](crate::ota::OtaProgress). The stream ends once the update
+    /// has completed or failed.
+    pub async fn ota_progress(
+        &self,
+        device: &crate::device::Device,
+    ) -> Result<impl Stream<Item = Result<crate::ota::OtaProgress, crate::Error>>, crate::Error> {
+        let socket = self.connect_events().await?;
+
+        Ok(crate::ota::ProgressWatcher::new(socket, device).into_stream())
+    }
+
+    /// Put the [`Hub`] in pairing mode for the given duration so new
+    /// [`Device`](crate::Device)s can join. The returned stream yields every
+    /// [`Device`](crate::Device) that joins as soon as the [`Hub`] announces it and ends when the
+    /// duration has passed or the connection to the [`Hub`] is lost, at which point pairing mode
+    /// is turned off again. A lost connection is yielded as the last item. Pairing can be stopped
+    /// early with [`Hub::stop_pairing`] or by dropping the stream, which turns pairing mode off
+    /// in the background.
+    pub async fn start_pairing(
+        &mut self,
+        duration: std::time::Duration,
+    ) -> anyhow::Result<impl Stream<Item = Result<crate::Device, crate::Error>>> {
+        let gateway_id = self.pairing_gateway().await?;
+
+        // Connect before pairing starts so no announcement is missed.
+        let socket = self.connect_events().await?;
+        self.set_permitting_join(&gateway_id, true).await?;
+
+        let deadline = tokio::time::Instant::now() + duration;
+
+        Ok(crate::pairing::Pairing::new(self.clone(), gateway_id, socket, deadline).into_stream())
+    }
+
+    /// Turn off pairing mode on the [`Hub`].
+    pub async fn stop_pairing(&mut self) -> anyhow::Result<()> {
+        let gateway_id = self.pairing_gateway().await?;
+        self.set_permitting_join(&gateway_id, false).await?;
+
+        Ok(())
+    }
+
+    /// Find the id of the gateway that can permit new [`Device`](crate::Device)s to join.
+    async fn pairing_gateway(&self) -> anyhow::Result<String> {
+        self.list()
+            .await?
+            .into_iter()
+            .find(|device| {
+                matches!(device, Device::Gateway(_))
+                    && device.can_receive(&[crate::device::Capability::PermittingJoin])
+            })
+            .map(|gateway| gateway.inner().id.clone())
+            .ok_or_else(|| anyhow::anyhow!("hub has no gateway that can permit joining"))
+    }
+
+    pub(crate) async fn set_permitting_join(
+        &self,
+        gateway_id: &str,
+        permitting_join: bool,
+    ) -> Result<(), crate::Error> {
+        let mut attributes = serde_json::Map::new();
+        attributes.insert("permittingJoin".to_string(), permitting_join.into());
+
+        self.patch_attributes(gateway_id, &attributes, None).await
+    }
+
+    /// Link a remote [`Device::Controller`](crate::Device::Controller) to the target
+    /// [`Device`](crate::Device)s so it controls them. Every target must be able to receive at
+    /// least one of the [`Capability`](crate::device::Capability)s the controller can send. The
+    /// function takes a mutable reference to the controller because on success its remote links
+    /// will be updated.
+    pub async fn link_remote(
+        &mut self,
+        controller: &mut crate::device::Device,
+        targets: &[&crate::device::Device],
+    ) -> anyhow::Result<()> {
+        if !matches!(controller, Device::Controller(_)) {
+            anyhow::bail!("device is not a remote");
+        }
+
+        let mut remote_links = controller.inner().remote_links.clone();
+
+        for target in targets {
+            let target_id = &target.inner().id;
+
+            if !controller.can_control(target) {
+                anyhow::bail!("remote cannot control device {target_id}");
+            }
+
+            if !remote_links.contains(target_id) {
+                remote_links.push(target_id.clone());
+            }
+        }
+
+        self.set_remote_links(controller, remote_links).await
+    }
+
+    /// Unlink a remote [`Device::Controller`](crate::Device::Controller) from the target
+    /// [`Device`](crate::Device)s. The function takes a mutable reference to the controller
+    /// because on success its remote links will be updated.
+    pub async fn unlink_remote(
+        &mut self,
+        controller: &mut crate::device::Device,
+        targets: &[&crate::device::Device],
+    ) -> anyhow::Result<()> {
+        if !matches!(controller, Device::Controller(_)) {
+            anyhow::bail!("device is not a remote");
+        }
+
+        let remote_links = controller
+            .inner()
+            .remote_links
+            .iter()
+            .filter(|id| !targets.iter().any(|target| &target.inner().id == *id))
+            .cloned()
+            .collect();
+
+        self.set_remote_links(controller, remote_links).await
+    }
+
+    /// Replace the remote links of a [`Device`](crate::Device).
+    async fn set_remote_links(
+        &mut self,
+        device: &mut crate::device::Device,
+        remote_links: Vec<String>,
+    ) -> anyhow::Result<()> {
+        let body = serde_json::to_string(&vec![serde_json::json!({
+            "remoteLinks": remote_links,
+        })])?;
+
+        self.client
+            .patch({
+                make_url(self.ip_address, &format!("/devices/{}", device.inner().id))?
+            })
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        device.inner_mut().remote_links = remote_links;
+
+        Ok(())
+    }
+
+    /// List all device sets that is known for the [`Hub`]. This will return an exhaustive list of
+    /// [`DeviceSet`](crate::device::DeviceSet)s.
+    pub async fn device_sets(&mut self) -> anyhow::Result<Vec<crate::device::DeviceSet>> {
+        self.client
+            .get({
+                make_url(self.ip_address, "/device-sets")?
+            })
+            .send()
+            .await?
+            .json::<Vec<crate::device::DeviceSet>>()
+            .await
+            .map_err(|err| anyhow::anyhow!(err))
+    }
+
+    /// Get a single [`DeviceSet`](crate::device::DeviceSet) based on its id.
+    pub async fn device_set(&mut self, id: &str) -> anyhow::Result<crate::device::DeviceSet> {
+        self.client
+            .get({
+                make_url(self.ip_address, &format!("/device-sets/{}", id))?
+            })
+            .send()
+            .await?
+            .json::<crate::device::DeviceSet>()
+            .await
+            .map_err(|err| anyhow::anyhow!(err))
+    }
+
+    /// Create a new, empty [`DeviceSet`](crate::device::DeviceSet). Add
+    /// [`Device`](crate::Device)s to it with [`Hub::add_to_device_set`].
+    pub async fn create_device_set(
+        &mut self,
+        name: &str,
+        icon: Option<&str>,
+    ) -> anyhow::Result<crate::device::DeviceSet> {
+        #[derive(serde::Deserialize)]
+        struct Created {
+            id: String,
+        }
+
+        let body = serde_json::to_string(&serde_json::json!({
+            "name": name,
+            "icon": icon,
+        }))?;
+
+        let created = self
+            .client
+            .post({
+                make_url(self.ip_address, "/device-sets")?
+            })
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Created>()
+            .await?;
+
+        Ok(crate::device::DeviceSet {
+            id: created.id,
+            name: name.to_string(),
+            icon: icon.map(String::from),
+            extra: Default::default(),
+        })
+    }
+
+    /// Rename a [`DeviceSet`](crate::device::DeviceSet). The function takes a mutable reference
+    /// to the [`DeviceSet`](crate::device::DeviceSet) because on successful renaming the passed
+    /// [`DeviceSet`](crate::device::DeviceSet) will be updated with the new name.
+    pub async fn rename_device_set(
+        &mut self,
+        device_set: &mut crate::device::DeviceSet,
+        new_name: &str,
+    ) -> anyhow::Result<()> {
+        let body = serde_json::to_string(&serde_json::json!({ "name": new_name }))?;
+
+        self.client
+            .patch({
+                make_url(self.ip_address, &format!("/device-sets/{}", device_set.id))?
+            })
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        device_set.name = new_name.to_string();
+
+        Ok(())
+    }
+
+    /// Delete a [`DeviceSet`](crate::device::DeviceSet). The [`Device`](crate::Device)s in the set
+    /// are kept.
+    pub async fn delete_device_set(
+        &mut self,
+        device_set: &crate::device::DeviceSet,
+    ) -> anyhow::Result<()> {
+        self.client
+            .delete({
+                make_url(self.ip_address, &format!("/device-sets/{}", device_set.id))?
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Add a [`Device`](crate::Device) to a [`DeviceSet`](crate::device::DeviceSet). The function
+    /// takes a mutable reference to the [`Device`](crate::Device) because on success the passed
+    /// [`Device`](crate::Device) will be updated with its new device sets.
+    pub async fn add_to_device_set(
+        &mut self,
+        device: &mut crate::device::Device,
+        device_set: &crate::device::DeviceSet,
+    ) -> anyhow::Result<()> {
+        if device.is_in_device_set(&device_set.id) {
+            return Ok(());
+        }
+
+        let mut device_sets = device.inner().device_set.clone().unwrap_or_default();
+        device_sets.push(device_set.clone());
+
+        self.set_device_sets(device, device_sets).await
+    }
+
+    /// Remove a [`Device`](crate::Device) from a [`DeviceSet`](crate::device::DeviceSet). The
+    /// function takes a mutable reference to the [`Device`](crate::Device) because on success the
+    /// passed [`Device`](crate::Device) will be updated with its remaining device sets.
+    pub async fn remove_from_device_set(
+        &mut self,
+        device: &mut crate::device::Device,
+        device_set: &crate::device::DeviceSet,
+    ) -> anyhow::Result<()> {
+        if !device.is_in_device_set(&device_set.id) {
+            return Ok(());
+        }
+
+        let device_sets = device
+            .inner()
+            .device_set
+            .iter()
+            .flatten()
+            .filter(|member_of| member_of.id != device_set.id)
+            .cloned()
+            .collect();
+
+        self.set_device_sets(device, device_sets).await
+    }
+
+    /// Replace the device sets of a [`Device`](crate::Device).
+    async fn set_device_sets(
+        &mut self,
+        device: &mut crate::device::Device,
+        device_sets: Vec<crate::device::DeviceSet>,
+    ) -> anyhow::Result<()> {
+        let ids = device_sets
+            .iter()
+            .map(|device_set| serde_json::json!({ "id": device_set.id }))
+            .collect::<Vec<_>>();
+
+        let body = serde_json::to_string(&vec![serde_json::json!({ "deviceSet": ids })])?;
+
+        self.client
+            .patch({
+                make_url(self.ip_address, &format!("/devices/{}", device.inner().id))?
+            })
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        device.inner_mut().device_set = Some(device_sets);
+
+        Ok(())
+    }
+
+    /// Turn every [`Device`](crate::Device) in a [`DeviceSet`](crate::device::DeviceSet) on or off
+    /// with a single request.
+    pub async fn device_set_on(
+        &mut self,
+        device_set: &crate::device::DeviceSet,
+        is_on: bool,
+    ) -> anyhow::Result<()> {
+        self.patch_device_set(device_set, &[("isOn", is_on.into())]).await
+    }
+
+    /// Set the light level of every light in a [`DeviceSet`](crate::device::DeviceSet) with a
+    /// single request, so they all change in sync.
+    pub async fn device_set_light_level(
+        &mut self,
+        device_set: &crate::device::DeviceSet,
+        level: u8,
+    ) -> anyhow::Result<()> {
+        if level > 100 {
+            anyhow::bail!("level must be between 0.0 -> 100.0");
+        }
+
+        self.patch_device_set(device_set, &[("lightLevel", level.into())]).await
+    }
+
+    /// Set the color temperature in Kelvin of every light in a
+    /// [`DeviceSet`](crate::device::DeviceSet) with a single request. The temperature must be
+    /// within the min and max of every light in the set that can change color temperature.
+    pub async fn device_set_temperature(
+        &mut self,
+        device_set: &crate::device::DeviceSet,
+        temperature: u16,
+    ) -> anyhow::Result<()> {
+        let devices = self.list().await?;
+        let lights = devices.iter().filter_map(|device| match device {
+            Device::Light(light)
+                if device.is_in_device_set(&device_set.id)
+                    && device.can_receive(&[crate::device::Capability::ColorTemperature]) =>
+            {
+                Some(light)
+            }
+            _ => None,
+        });
+
+        let mut any_light = false;
+        for light in lights {
+            any_light = true;
+
+            let (Some(min), Some(max)) = (
+                light.attributes.color_temperature_min,
+                light.attributes.color_temperature_max,
+            ) else {
+                continue;
+            };
+
+            // The hub reports the coldest temperature as min.
+            if !(min.min(max)..=min.max(max)).contains(&temperature) {
+                anyhow::bail!(
+                    "color temperature {temperature} not within {min} -> {max} of {}",
+                    light.id
+                );
+            }
+        }
+
+        if !any_light {
+            anyhow::bail!("no light in device set can set color temperature");
+        }
+
+        self.patch_device_set(device_set, &[("colorTemperature", temperature.into())])
+            .await
+    }
+
+    /// Set hue and saturation of every light in a [`DeviceSet`](crate::device::DeviceSet) with a
+    /// single request.
+    pub async fn device_set_hue_saturation(
+        &mut self,
+        device_set: &crate::device::DeviceSet,
+        hue: f64,
+        saturation: f64,
+    ) -> anyhow::Result<()> {
+        if !(0f64..=360f64).contains(&hue) {
+            anyhow::bail!("hue must be between 0.0 -> 360.0");
+        }
+
+        if !(0f64..=1f64).contains(&saturation) {
+            anyhow::bail!("saturation must be between 0.0 -> 1.0");
+        }
+
+        self.patch_device_set(
+            device_set,
+            &[("colorHue", hue.into()), ("colorSaturation", saturation.into())],
+        )
+        .await
+    }
+
+    /// Send attributes to every [`Device`](crate::Device) in a
+    /// [`DeviceSet`](crate::device::DeviceSet) with a single request.
+    async fn patch_device_set(
+        &self,
+        device_set: &crate::device::DeviceSet,
+        attributes: &[(&str, serde_json::Value)],
+    ) -> anyhow::Result<()> {
+        let attributes: serde_json::Map<String, serde_json::Value> = attributes
+            .iter()
+            .map(|(attribute, value)| (attribute.to_string(), value.clone()))
+            .collect();
+
+        self.patch(&format!("/devices/set/{}", device_set.id), &attributes, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /*/// Trigger a [`Scene`](crate::Scene) now. Will work independent of a scheduled scene or not.
+    pub async fn trigger_scene(&mut self, scene: &crate::scene::Scene) -> anyhow::Result<()> {
+        let inner = scene.inner();
+
+        self.client
+            .call(self.create_request(
+                http::Method::POST,
+                format!("/scenes/{}/trigger", inner.id).as_str(),
+                Some(hyper::Body::empty()),
+            )?)
+            .await?;
+
+        Ok(())
+    }*/
+
+    /*/// Undo scene will revert the changes set by the [`Scene`](crate::Scene).
+    pub async fn undo_scene(&mut self, scene: &crate::scene::Scene) -> anyhow::Result<()> {
+        let inner = scene.inner();
+
+        self.client
+            .call(self.create_request(
+                http::Method::POST,
+                format!("/scenes/{}/undo", inner.id).as_str(),
+                Some(hyper::Body::empty()),
+            )?)
+            .await?;
+
+        Ok(())
+    }*/
+}
+
+fn make_url(host: Ipv4Addr, path: &str) -> Result<url::Url, crate::Error> {
+    use Part::*;
+
+    url_builder! {
+        Scheme("https");
         HostIpv4(host);
         Port(DIRIGERA_PORT);
         Path(&format!("{}{}", DIRIGERA_API_VERSION, path));
@@ -492,10 +1688,90 @@ fn make_url(host: Ipv4Addr, path: &str) -> Result<url::Url, crate::Error> {
     .map_err(crate::Error::UrlBuilder)
 }
 
-fn has_capability(
+/// How [`Hub::identify`] makes a [`Device`] identify itself.
+#[derive(Debug, PartialEq)]
+enum Identify {
+    /// The [`Device`] can identify itself.
+    Native,
+    /// The light is flashed and then restored with the given attributes.
+    Flash(serde_json::Map<String, serde_json::Value>),
+}
+
+impl Identify {
+    fn for_device(device: &Device) -> anyhow::Result<Self> {
+        let can_receive = device.inner().capabilities.can_receive.as_slice();
+
+        if has_capability(can_receive, &[crate::device::Capability::Identify]) {
+            return Ok(Self::Native);
+        }
+
+        let Device::Light(light) = device else {
+            anyhow::bail!("identify not supported by device");
+        };
+
+        if !has_capability(can_receive, &[crate::device::Capability::IsOn]) {
+            anyhow::bail!("identify not supported by device");
+        }
+
+        let is_on = light.attributes.is_on;
+
+        let mut restore = serde_json::Map::new();
+        restore.insert("isOn".to_string(), is_on.into());
+
+        let dimmable = has_capability(can_receive, &[crate::device::Capability::LightLevel]);
+        if let Some(level) = light.attributes.light_level.filter(|_| is_on && dimmable) {
+            restore.insert("lightLevel".to_string(), level.into());
+        }
+
+        Ok(Self::Flash(restore))
+    }
+}
+
+pub(crate) fn has_capability(
     got: &[crate::device::Capability],
     required: &[crate::device::Capability],
 ) -> bool {
     required.iter().all(|item| got.contains(item))
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(fixture: &str, can_receive: &[&str]) -> Device {
+        let mut device: serde_json::Value = serde_json::from_str(fixture).unwrap();
+        device["capabilities"]["canReceive"] = can_receive.into();
+
+        serde_json::from_value(device).unwrap()
+    }
+
+    #[test]
+    fn identify_with_capability() {
+        let light = device(include_str!("../tests/fixtures/light.json"), &["isOn", "identify"]);
+
+        assert_eq!(Identify::for_device(&light).unwrap(), Identify::Native);
+    }
+
+    #[test]
+    fn identify_by_flashing() {
+        // The fixture has an `identifyPeriod` attribute, which doesn't make it identifiable.
+        let light = device(
+            include_str!("../tests/fixtures/light.json"),
+            &["isOn", "lightLevel"],
+        );
+
+        let Identify::Flash(restore) = Identify::for_device(&light).unwrap() else {
+            panic!("light should be flashed");
+        };
+        assert_eq!(restore["isOn"], true);
+        assert_eq!(restore["lightLevel"], 80);
+    }
+
+    #[test]
+    fn identify_unsupported() {
+        let outlet = device(include_str!("../tests/fixtures/outlet.json"), &["isOn"]);
+
+        assert!(Identify::for_device(&outlet).is_err());
+    }
+}
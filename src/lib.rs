@@ -2,16 +2,29 @@
 //! Dirigera is a client to communicate with your IKEA Dirigera hub and control your Trådfri
 //! devices. ~~It is built with [`hyper`] and is bundled with an optional tool to generate the token
 //! you need for the communication.~~
-mod device;
+pub mod color;
+pub mod device;
 mod hub;
+pub mod diff;
+pub mod environment;
+pub mod events;
+pub mod group;
+pub mod handle;
+pub mod ota;
+mod pairing;
+pub mod room;
 pub mod scene;
+mod state;
+pub mod watch;
 pub mod traits;
+pub mod update;
 mod connect;
 mod config;
 mod errors;
 
 pub use hub::Hub;
-pub use errors::Error;
+pub use state::HubState;
+pub use errors::{DeviceParseError, Error};
 pub use config::Config;
 pub use connect::Connect;
 pub use device::{
@@ -19,7 +32,9 @@ pub use device::{
     DeviceData,
     DeviceType
 };
+pub use color::Color;
 pub use scene::Scene;
+pub use events::Event;
 
 use std::sync::OnceLock;
 use serde::Deserialize;
@@ -56,9 +71,56 @@ pub(crate) fn deserialize_datetime_optional<'de, D>(
 where
     D: serde::Deserializer<'de>,
 {
-    match deserialize_datetime(deserializer) {
-        Ok(system_time) => Ok(Some(system_time)),
-        Err(_) => Ok(None),
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+
+    Ok(value.and_then(|value| value.as_str()?.parse().ok()))
+}
+
+
+pub(crate) fn serialize_datetime<S>(
+    datetime: &chrono::DateTime<chrono::Utc>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(&datetime.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+}
+
+pub(crate) fn serialize_datetime_optional<S>(
+    datetime: &Option<chrono::DateTime<chrono::Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match datetime {
+        Some(datetime) => serialize_datetime(datetime, serializer),
+        None => serializer.serialize_none(),
     }
 }
 
+/// Serialize whole numbers as integers, the way the hub sends them, to keep round-trips lossless.
+pub(crate) fn serialize_number<S>(number: f64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        serializer.serialize_i64(number as i64)
+    } else {
+        serializer.serialize_f64(number)
+    }
+}
+
+pub(crate) fn serialize_number_optional<S>(
+    number: &Option<f64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match number {
+        Some(number) => serialize_number(*number, serializer),
+        None => serializer.serialize_none(),
+    }
+}
@@ -0,0 +1,271 @@
+//! Firmware is updated over the air (OTA). Each [`Device`] reports its [`OtaPolicy`], the window
+//! updates may be installed in and the [`OtaState`] and [`OtaStatus`] of updates. The
+//! [`Hub`](crate::Hub) can check for updates, change policy and window, and follow an update in
+//! progress with [`Hub::ota_progress`](crate::Hub::ota_progress).
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::events::Socket;
+use crate::Device;
+use crate::Error;
+use crate::Event;
+
+/// When the hub installs firmware updates.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub enum OtaPolicy {
+    /// Download and install updates within the schedule window.
+    AutoUpdate,
+    /// Download updates but wait for them to be installed manually.
+    AutoDownload,
+    /// Any policy not known by this crate.
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// Where a [`Device`] is in the process of updating its firmware.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub enum OtaState {
+    ReadyToCheck,
+    CheckInProgress,
+    ReadyToDownload,
+    DownloadInProgress,
+    DownloadFailed,
+    ReadyToUpdate,
+    UpdateInProgress,
+    UpdateFailed,
+    UpdateComplete,
+    /// Any state not known by this crate.
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl OtaState {
+    /// Whether a check, download or update is running.
+    pub fn is_in_progress(&self) -> bool {
+        matches!(
+            self,
+            Self::CheckInProgress | Self::DownloadInProgress | Self::UpdateInProgress
+        )
+    }
+
+    /// Whether an update has either completed or failed.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            Self::DownloadFailed | Self::UpdateFailed | Self::UpdateComplete
+        )
+    }
+}
+
+/// Whether a [`Device`] runs the latest firmware.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd)]
+#[serde(rename_all = "camelCase")]
+pub enum OtaStatus {
+    UpToDate,
+    UpdateAvailable,
+    /// Any status not known by this crate.
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// A time of day in hours and minutes, sent by the hub as `HH:MM`. Used for the window updates
+/// may be installed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay(pub chrono::NaiveTime);
+
+impl TimeOfDay {
+    /// Create a [`TimeOfDay`], returns `None` if hour or minute is out of range.
+    pub fn new(hour: u32, minute: u32) -> Option<Self> {
+        chrono::NaiveTime::from_hms_opt(hour, minute, 0).map(Self)
+    }
+}
+
+impl std::fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0.format("%H:%M"))
+    }
+}
+
+impl std::str::FromStr for TimeOfDay {
+    type Err = chrono::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        chrono::NaiveTime::parse_from_str(s, "%H:%M").map(Self)
+    }
+}
+
+impl Serialize for TimeOfDay {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let time = String::deserialize(deserializer)?;
+        time.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A time in the window updates may be installed in, as sent by the hub. Anything that isn't a
+/// [`TimeOfDay`] is kept as is so an odd value doesn't fail the whole [`Device`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd)]
+#[serde(untagged)]
+pub enum ScheduleTime {
+    Time(TimeOfDay),
+    /// Any value not known by this crate.
+    Other(String),
+}
+
+impl ScheduleTime {
+    /// The [`TimeOfDay`], `None` if the hub sent something else.
+    pub fn time(&self) -> Option<TimeOfDay> {
+        match self {
+            Self::Time(time) => Some(*time),
+            Self::Other(_) => None,
+        }
+    }
+}
+
+impl From<TimeOfDay> for ScheduleTime {
+    fn from(time: TimeOfDay) -> Self {
+        Self::Time(time)
+    }
+}
+
+/// The OTA state of a [`Device`] at one point of an update.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtaProgress {
+    pub state: OtaState,
+    pub status: OtaStatus,
+    /// Progress of the current download or update in percent.
+    pub progress: u8,
+}
+
+/// State behind [`Hub::ota_progress`](crate::Hub::ota_progress).
+pub(crate) struct ProgressWatcher {
+    socket: Socket,
+    id: String,
+    current: OtaProgress,
+    done: bool,
+}
+
+impl ProgressWatcher {
+    pub(crate) fn new(socket: Socket, device: &Device) -> Self {
+        let common = device.common_attributes();
+
+        Self {
+            socket,
+            id: device.inner().id.clone(),
+            current: OtaProgress {
+                state: common.ota_state.clone(),
+                status: common.ota_status.clone(),
+                progress: common.ota_progress,
+            },
+            done: false,
+        }
+    }
+
+    pub(crate) fn into_stream(self) -> impl Stream<Item = Result<OtaProgress, Error>> {
+        futures::stream::unfold(self, |mut watcher| async move {
+            let item = watcher.next().await?;
+            Some((item, watcher))
+        })
+    }
+
+    async fn next(&mut self) -> Option<Result<OtaProgress, Error>> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            let Some(message) = self.socket.next().await else {
+                self.done = true;
+                return Some(Err(Error::ConnectionClosed));
+            };
+
+            let state = match crate::events::decode(message) {
+                Some(Ok(Event::DeviceStateChanged(state))) if state.id == self.id => state,
+                Some(Err(err)) => return Some(Err(err)),
+                _ => continue,
+            };
+
+            let mut next = self.current.clone();
+            if let Err(err) = merge(&mut next, &state.attributes) {
+                return Some(Err(err.into()));
+            }
+
+            if next == self.current {
+                continue;
+            }
+
+            self.done = next.state.is_finished()
+                || (next.status == OtaStatus::UpToDate && self.current.status != next.status);
+            self.current = next.clone();
+
+            return Some(Ok(next));
+        }
+    }
+}
+
+fn merge(
+    progress: &mut OtaProgress,
+    attributes: &serde_json::Map<String, serde_json::Value>,
+) -> Result<(), serde_json::Error> {
+    if let Some(state) = attributes.get("otaState") {
+        progress.state = OtaState::deserialize(state)?;
+    }
+
+    if let Some(status) = attributes.get("otaStatus") {
+        progress.status = OtaStatus::deserialize(status)?;
+    }
+
+    if let Some(value) = attributes.get("otaProgress") {
+        progress.progress = u8::deserialize(value)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_of_day() {
+        let time: TimeOfDay = "23:05".parse().unwrap();
+
+        assert_eq!(time, TimeOfDay::new(23, 5).unwrap());
+        assert_eq!(time.to_string(), "23:05");
+        assert_eq!(TimeOfDay::new(4, 0).unwrap().to_string(), "04:00");
+
+        assert!("24:00".parse::<TimeOfDay>().is_err());
+        assert!("".parse::<TimeOfDay>().is_err());
+        assert!(TimeOfDay::new(12, 60).is_none());
+    }
+
+    #[test]
+    fn schedule_time() {
+        let parse = |value: &str| serde_json::from_str::<Option<ScheduleTime>>(value).unwrap();
+
+        assert_eq!(
+            parse(r#""02:30""#),
+            Some(ScheduleTime::Time(TimeOfDay::new(2, 30).unwrap()))
+        );
+        assert_eq!(parse(r#""02:30:00""#), Some(ScheduleTime::Other("02:30:00".to_string())));
+        assert_eq!(parse(r#""""#), Some(ScheduleTime::Other(String::new())));
+        assert_eq!(parse("null"), None);
+
+        // Values not understood are sent back unchanged.
+        let other = ScheduleTime::Other("02:30:00".to_string());
+        assert_eq!(serde_json::to_string(&other).unwrap(), r#""02:30:00""#);
+    }
+}
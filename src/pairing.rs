@@ -0,0 +1,99 @@
+//! New devices are added by putting the hub in pairing mode with
+//! [`Hub::start_pairing`](crate::Hub::start_pairing), which lets devices join for a limited time.
+//! Every [`Device`] that joins is yielded by the returned stream as soon as the hub announces it,
+//! so it can be named and placed in a room right away.
+use futures::{Stream, StreamExt};
+use tokio::time::Instant;
+
+use crate::events::Socket;
+use crate::Device;
+use crate::Error;
+use crate::Event;
+use crate::Hub;
+
+/// State behind [`Hub::start_pairing`](crate::Hub::start_pairing).
+pub(crate) struct Pairing {
+    hub: Hub,
+    gateway_id: String,
+    socket: Socket,
+    deadline: Instant,
+    done: bool,
+    /// The error that ended the connection, yielded after pairing mode is turned off.
+    error: Option<Error>,
+}
+
+impl Pairing {
+    pub(crate) fn new(hub: Hub, gateway_id: String, socket: Socket, deadline: Instant) -> Self {
+        Self {
+            hub,
+            gateway_id,
+            socket,
+            deadline,
+            done: false,
+            error: None,
+        }
+    }
+
+    pub(crate) fn into_stream(self) -> impl Stream<Item = Result<Device, Error>> {
+        futures::stream::unfold(self, |mut pairing| async move {
+            let item = pairing.next().await?;
+            Some((item, pairing))
+        })
+    }
+
+    async fn next(&mut self) -> Option<Result<Device, Error>> {
+        if self.done {
+            return self.error.take().map(Err);
+        }
+
+        loop {
+            // Without a connection no more devices can be announced, so pairing stops just like
+            // when the duration has passed.
+            let message = match tokio::time::timeout_at(self.deadline, self.socket.next()).await {
+                Ok(Some(Ok(message))) => message,
+                Ok(Some(Err(err))) => {
+                    self.error = Some(err.into());
+                    return self.stop().await;
+                }
+                Ok(None) | Err(_) => return self.stop().await,
+            };
+
+            match crate::events::decode(Ok(message)) {
+                Some(Ok(Event::DeviceAdded(device))) => return Some(Ok(*device)),
+                Some(Err(err)) => return Some(Err(err)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Turn off pairing mode and end the stream. A failure to do so is yielded before the error
+    /// that ended the connection, if any.
+    async fn stop(&mut self) -> Option<Result<Device, Error>> {
+        self.done = true;
+
+        match self.hub.set_permitting_join(&self.gateway_id, false).await {
+            Ok(()) => self.error.take().map(Err),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+impl Drop for Pairing {
+    /// A stream dropped before it ended would leave the hub accepting new devices, so pairing
+    /// mode is turned off in the background. Without a runtime it's left to the hub to end it
+    /// when the duration has passed.
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let hub = self.hub.clone();
+            let gateway_id = std::mem::take(&mut self.gateway_id);
+
+            runtime.spawn(async move {
+                let _ = hub.set_permitting_join(&gateway_id, false).await;
+            });
+        }
+    }
+}
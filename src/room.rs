@@ -0,0 +1,65 @@
+//! A [`Room`](crate::device::Room) groups [`Device`]s the way they're placed in the home. The
+//! [`Hub`](crate::Hub) can send a command to every capable device in a room, f.ex.
+//! [`Hub::room_set_on`](crate::Hub::room_set_on), and [`RoomState`] summarizes the state of all
+//! devices in a room.
+use crate::Device;
+
+/// The aggregated state of all [`Device`]s in a [`Room`](crate::device::Room).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoomState {
+    /// Number of [`Device`]s in the room.
+    pub devices: usize,
+    /// Number of lights that are on.
+    pub lights_on: usize,
+    /// Average light level of the lights that are on, `None` if all lights are off.
+    pub average_light_level: Option<f64>,
+    /// Number of [`Device`]s the hub can't reach.
+    pub unreachable: usize,
+    /// The lowest battery level of the battery powered [`Device`]s, `None` if there are none.
+    pub min_battery_percentage: Option<i8>,
+}
+
+impl RoomState {
+    /// Aggregate the state of the [`Device`]s belonging to the room with the given id. Any
+    /// [`Device`] in another room is ignored.
+    pub fn from_devices<'a>(room_id: &str, devices: impl IntoIterator<Item = &'a Device>) -> Self {
+        let mut state = Self::default();
+        let mut light_levels = Vec::new();
+
+        for device in devices.into_iter().filter(|device| device.is_in_room(room_id)) {
+            state.devices += 1;
+
+            if !device.inner().is_reachable {
+                state.unreachable += 1;
+            }
+
+            if let Some(battery) = device.battery_percentage() {
+                state.min_battery_percentage = Some(
+                    state
+                        .min_battery_percentage
+                        .map_or(battery, |min| min.min(battery)),
+                );
+            }
+
+            if let Device::Light(light) = device {
+                let attributes = &light.attributes;
+                if let (true, Some(level)) = (attributes.is_on, attributes.light_level) {
+                    light_levels.push(f64::from(level));
+                }
+            }
+        }
+
+        state.lights_on = light_levels.len();
+        if !light_levels.is_empty() {
+            state.average_light_level =
+                Some(light_levels.iter().sum::<f64>() / light_levels.len() as f64);
+        }
+
+        state
+    }
+
+    /// Whether any light in the room is on.
+    pub fn any_light_on(&self) -> bool {
+        self.lights_on > 0
+    }
+}
@@ -1,40 +1,72 @@
 //! With the IKEA Home Smart app you can configure scenes that can be either triggered manually or
 //! on a schedule. Scenes are specific configuration for a set of devices such as color
 //! temperature, light level, blind level etcetera.
-use crate::{deserialize_datetime, deserialize_datetime_optional};
-use serde::Deserialize;
+//!
+//! Like [`Device`](crate::Device)s, scenes serialize to the same format as read from the hub with
+//! any unknown fields preserved in `extra`.
+use crate::{
+    deserialize_datetime,
+    deserialize_datetime_optional,
+    serialize_datetime,
+    serialize_datetime_optional,
+};
+use serde::{Deserialize, Serialize};
 
 /// A [`Scene`] is represented by its `type` and will hold all the [`SceneData`].
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum Scene {
     UserScene(SceneData),
+    /// Any scene type not known by this crate. The type is kept in
+    /// [`SceneData::scene_type`].
+    #[serde(untagged)]
+    Other(SceneData),
 }
 
 /// Specific data for a scene such as what actions it will do and what [`Trigger`]s it has.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SceneData {
     pub id: String,
     pub info: Info,
-    #[serde(alias = "type")]
+    #[serde(rename = "type", alias = "sceneType", skip_serializing_if = "Option::is_none")]
     pub scene_type: Option<String>,
     pub actions: Vec<Action>,
     pub commands: Vec<String>,
     pub triggers: Vec<Trigger>,
     pub undo_allowed_duration: u8,
-    #[serde(deserialize_with = "deserialize_datetime")]
+    #[serde(
+        deserialize_with = "deserialize_datetime",
+        serialize_with = "serialize_datetime"
+    )]
     pub created_at: chrono::DateTime<chrono::Utc>,
-    #[serde(default, deserialize_with = "deserialize_datetime_optional")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_datetime_optional",
+        serialize_with = "serialize_datetime_optional",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub last_completed: Option<chrono::DateTime<chrono::Utc>>,
-    #[serde(default, deserialize_with = "deserialize_datetime_optional")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_datetime_optional",
+        serialize_with = "serialize_datetime_optional",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub last_triggered: Option<chrono::DateTime<chrono::Utc>>,
-    #[serde(default, deserialize_with = "deserialize_datetime_optional")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_datetime_optional",
+        serialize_with = "serialize_datetime_optional",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub last_undo: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Each scene has a name and icon which is represented under the scene [`Info`].
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Info {
     pub name: String,
@@ -43,53 +75,77 @@ pub struct Info {
 
 /// A scene can be triggered from the app (or API), based on sunrise or sunset or on a specific
 /// time.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum Trigger {
     App(AppTrigger),
     SunriseSunset(SunriseSunsetTrigger),
     Time(TimeTrigger),
+    /// Any trigger type not known by this crate, kept as is.
+    #[serde(untagged)]
+    Other(serde_json::Value),
 }
 
 /// Events triggered from the app shows the state and when it was triggered.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct AppTrigger {
     pub id: String,
     pub disabled: bool,
-    #[serde(default, deserialize_with = "deserialize_datetime_optional")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_datetime_optional",
+        serialize_with = "serialize_datetime_optional",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub triggered_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Events triggered on time will show when the next trigger will happen and what [`EndTrigger`] the
 /// schedule has.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TimeTrigger {
     pub id: String,
     pub disabled: bool,
-    #[serde(default, deserialize_with = "deserialize_datetime")]
-    pub next_trigger_at: chrono::DateTime<chrono::Utc>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_datetime_optional",
+        serialize_with = "serialize_datetime_optional",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub next_trigger_at: Option<chrono::DateTime<chrono::Utc>>,
     pub trigger: Time,
     pub end_trigger_event: EndTrigger,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Sunrise and sunset events will sync with the user's location and the response will show when
 /// the next trigger will happen and what [`EndTrigger`] the schedule has.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SunriseSunsetTrigger {
     pub id: String,
     pub disabled: bool,
-    #[serde(default, deserialize_with = "deserialize_datetime")]
-    pub next_trigger_at: chrono::DateTime<chrono::Utc>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_datetime_optional",
+        serialize_with = "serialize_datetime_optional",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub next_trigger_at: Option<chrono::DateTime<chrono::Utc>>,
     pub trigger: Follow,
     pub end_trigger_event: EndTrigger,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// An [`EndTrigger`] is something that will trigger the scene to end. It can be based on a
 /// duration, sunrise or sunset or a specific time.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type", content = "trigger")]
 pub enum EndTrigger {
     Duration(Duration),
@@ -98,7 +154,7 @@ pub enum EndTrigger {
 }
 
 /// Duration is just number of seconds from the trigger start.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Duration {
     pub duration: u32,
@@ -106,51 +162,63 @@ pub struct Duration {
 
 /// Sunrise and sunset shows what days to trigger for sunrise or sunset if specific days and any
 /// offset from the sunrise or sunset time.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum Follow {
     Sunrise {
+        #[serde(skip_serializing_if = "Option::is_none")]
         days: Option<Vec<String>>,
         offset: i32,
     },
     Sunset {
+        #[serde(skip_serializing_if = "Option::is_none")]
         days: Option<Vec<String>>,
         offset: i32,
     },
 }
 
 /// Time shows what days to trigger for the specific time and what time that is.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Time {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub days: Option<Vec<String>>,
     pub time: String,
 }
 
 /// A scene has a type to target for its action.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum Action {
     Device(ActionData),
+    /// Any action type not known by this crate, kept as is.
+    #[serde(untagged)]
+    Other(serde_json::Value),
 }
 
 /// Data for the action type which holds the [`Device`](crate::Device) id and attribute for the [`Scene`].
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ActionData {
     pub id: String,
     pub device_id: String,
     pub attributes: SceneAttributes,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Attributes to the scene which shows information about on or off state and light level and color
 /// temperature for [`Device`](crate::Device)s that support those.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SceneAttributes {
     pub is_on: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub light_level: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub color_temperature: Option<u16>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl Scene {
@@ -158,6 +226,7 @@ impl Scene {
     pub fn inner(&self) -> &SceneData {
         match self {
             Scene::UserScene(inner) => inner,
+            Scene::Other(inner) => inner,
         }
     }
 }
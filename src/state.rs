@@ -0,0 +1,282 @@
+//! Instead of fetching every [`Device`] each time something needs to be read, a [`HubState`]
+//! keeps a local mirror of the whole home. It's loaded once from the [`Hub`] and then kept in sync
+//! by applying [`Event`]s or the result of a new poll.
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+use tokio::sync::watch;
+
+use crate::traits::DirigeraExt;
+use crate::Device;
+use crate::Event;
+use crate::Hub;
+use crate::Scene;
+
+/// A local mirror of all [`Device`]s and [`Scene`]s known to the [`Hub`], keyed by their id.
+/// Reads are synchronous and cheap, and each [`Device`] can be watched for changes on its own.
+#[derive(Debug, Default)]
+pub struct HubState {
+    devices: RwLock<HashMap<String, watch::Sender<Device>>>,
+    scenes: RwLock<HashMap<String, Scene>>,
+}
+
+impl HubState {
+    /// Create a [`HubState`] with the current [`Device`]s and [`Scene`]s from the [`Hub`].
+    pub async fn load(hub: &mut Hub) -> anyhow::Result<Self> {
+        let state = Self::default();
+
+        state.replace_devices(hub.list().await?);
+        state.replace_scenes(hub.scenes().await?);
+
+        Ok(state)
+    }
+
+    /// Get a copy of the [`Device`] with the given id.
+    pub fn device(&self, id: &str) -> Option<Device> {
+        self.devices
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .map(|device| device.borrow().clone())
+    }
+
+    /// Get a copy of all [`Device`]s.
+    pub fn devices(&self) -> Vec<Device> {
+        self.devices
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .map(|device| device.borrow().clone())
+            .collect()
+    }
+
+    /// Get a copy of the [`Scene`] with the given id.
+    pub fn scene(&self, id: &str) -> Option<Scene> {
+        self.scenes
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .cloned()
+    }
+
+    /// Get a copy of all [`Scene`]s.
+    pub fn scenes(&self) -> Vec<Scene> {
+        self.scenes
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Watch a single [`Device`] for changes. The receiver is notified every time the
+    /// [`Device`] changes and is closed if the [`Device`] is removed from the [`Hub`].
+    pub fn watch(&self, id: &str) -> Option<watch::Receiver<Device>> {
+        self.devices
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .map(watch::Sender::subscribe)
+    }
+
+    /// Apply an [`Event`] from the [`Hub`] to the mirror. A
+    /// [`Event::Reconnected`](crate::Event::Reconnected) replaces all [`Device`]s since
+    /// events may have been missed while disconnected.
+    pub fn apply(&self, event: &Event) -> Result<(), crate::Error> {
+        match event {
+            Event::DeviceStateChanged(state) => {
+                let devices = self.devices.read().unwrap_or_else(PoisonError::into_inner);
+                let Some(device) = devices.get(&state.id) else {
+                    return Ok(());
+                };
+
+                // Merge into a copy so a failed merge leaves the device untouched and watchers
+                // are only notified of complete changes.
+                let mut result = Ok(());
+                device.send_if_modified(|device| {
+                    let mut updated = device.clone();
+                    let inner = updated.inner_mut();
+
+                    if let Some(is_reachable) = state.is_reachable {
+                        inner.is_reachable = is_reachable;
+                    }
+
+                    if let Some(last_seen) = state.last_seen {
+                        inner.last_seen = last_seen;
+                    }
+
+                    result = updated.merge_attributes(&state.attributes);
+                    if result.is_err() {
+                        return false;
+                    }
+
+                    *device = updated;
+                    true
+                });
+
+                result?;
+            }
+            Event::DeviceAdded(device) => self.upsert_device((**device).clone()),
+            Event::DeviceRemoved(reference) => {
+                self.devices
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .remove(&reference.id);
+            }
+            Event::SceneCreated(scene) | Event::SceneUpdated(scene) => {
+                self.scenes
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(scene.inner().id.clone(), scene.clone());
+            }
+            Event::SceneDeleted(reference) => {
+                self.scenes
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .remove(&reference.id);
+            }
+            Event::Reconnected(devices) => self.replace_devices(devices.clone()),
+            Event::SceneTriggered(_) | Event::Disconnected(_) | Event::Unknown { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    /// Replace all [`Device`]s with a fresh list, f.ex. from polling
+    /// [`DirigeraExt::list`](crate::traits::DirigeraExt::list). Watchers are only notified for
+    /// [`Device`]s that actually changed and [`Device`]s no longer in the list are removed.
+    pub fn replace_devices(&self, devices: Vec<Device>) {
+        let mut current = self.devices.write().unwrap_or_else(PoisonError::into_inner);
+        let mut next = HashMap::with_capacity(devices.len());
+
+        for device in devices {
+            let id = device.inner().id.clone();
+            let sender = match current.remove(&id) {
+                Some(sender) => {
+                    sender.send_if_modified(|current| {
+                        if *current == device {
+                            return false;
+                        }
+
+                        *current = device;
+                        true
+                    });
+                    sender
+                }
+                None => watch::channel(device).0,
+            };
+
+            next.insert(id, sender);
+        }
+
+        *current = next;
+    }
+
+    /// Replace all [`Scene`]s with a fresh list.
+    pub fn replace_scenes(&self, scenes: Vec<Scene>) {
+        *self.scenes.write().unwrap_or_else(PoisonError::into_inner) = scenes
+            .into_iter()
+            .map(|scene| (scene.inner().id.clone(), scene))
+            .collect();
+    }
+
+    fn upsert_device(&self, device: Device) {
+        let mut devices = self.devices.write().unwrap_or_else(PoisonError::into_inner);
+
+        match devices.get(&device.inner().id) {
+            Some(sender) => {
+                sender.send_replace(device);
+            }
+            None => {
+                devices.insert(device.inner().id.clone(), watch::channel(device).0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::DeviceState;
+
+    /// Id of the light in the fixture.
+    const ID: &str = "7f1a6c0e-1a4f-4d0b-9f5a-2b0d5c1e8a11_1";
+
+    fn state() -> HubState {
+        let light = serde_json::from_str(include_str!("../tests/fixtures/light.json")).unwrap();
+        let state = HubState::default();
+        state.replace_devices(vec![light]);
+
+        state
+    }
+
+    fn changed(id: &str, attributes: serde_json::Value) -> Event {
+        Event::DeviceStateChanged(DeviceState {
+            id: id.to_string(),
+            is_reachable: Some(false),
+            last_seen: None,
+            attributes: attributes.as_object().unwrap().clone(),
+        })
+    }
+
+    #[test]
+    fn apply_device_state_changed() {
+        let state = state();
+        let mut watcher = state.watch(ID).unwrap();
+        watcher.mark_unchanged();
+
+        state
+            .apply(&changed(ID, serde_json::json!({ "isOn": false, "lightLevel": 20 })))
+            .unwrap();
+
+        let Some(Device::Light(light)) = state.device(ID) else {
+            panic!("light is gone");
+        };
+
+        assert!(!light.attributes.is_on);
+        assert_eq!(light.attributes.light_level, Some(20));
+        assert!(!light.is_reachable);
+        assert!(watcher.has_changed().unwrap());
+    }
+
+    #[test]
+    fn apply_failed_merge_leaves_device_untouched() {
+        let state = state();
+        let before = state.device(ID).unwrap();
+        let mut watcher = state.watch(ID).unwrap();
+        watcher.mark_unchanged();
+
+        let result = state.apply(&changed(
+            ID,
+            serde_json::json!({ "isOn": false, "lightLevel": "bright" }),
+        ));
+
+        assert!(result.is_err());
+        assert_eq!(state.device(ID).unwrap(), before);
+        assert!(!watcher.has_changed().unwrap());
+    }
+
+    #[test]
+    fn apply_unknown_device() {
+        let state = state();
+        let before = state.devices();
+
+        state
+            .apply(&changed("unknown", serde_json::json!({ "isOn": false })))
+            .unwrap();
+
+        assert_eq!(state.devices(), before);
+    }
+
+    #[test]
+    fn apply_device_removed() {
+        let state = state();
+        let watcher = state.watch(ID).unwrap();
+
+        state
+            .apply(&Event::DeviceRemoved(crate::events::Reference { id: ID.to_string() }))
+            .unwrap();
+
+        assert_eq!(state.device(ID), None);
+        assert!(watcher.has_changed().is_err());
+    }
+}
@@ -0,0 +1,456 @@
+//! Setting several attributes with the single purpose methods on the [`Hub`] sends one request
+//! per attribute, which f.ex. makes a light visibly flicker when it's turned on, dimmed and given
+//! a new color temperature. An [`Update`] collects all attributes and sends them in one request.
+//! The change can be made gradually either by the hub with [`Update::transition`] or, for devices
+//! that ignore the transition time, client-side with [`Update::fade`].
+//!
+//! ```no_run
+//! # async fn example(hub: &mut dirigera::Hub, device: &mut dirigera::Device) -> anyhow::Result<()> {
+//! hub.update(device)
+//!     .on(true)
+//!     .light_level(40)
+//!     .color_temperature(2700)
+//!     .send()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::time::Duration;
+use serde_json::{Map, Value};
+use tokio::time::MissedTickBehavior;
+
+use crate::device::{Capability, ColorMode, Startup};
+use crate::Device;
+use crate::Hub;
+
+/// Shortest time between two steps of [`Update::fade`], to not flood the hub with requests.
+const FADE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A set of attribute changes for a single [`Device`], created with
+/// [`Hub::update`](crate::Hub::update). Nothing is validated or sent until [`Update::send`] is
+/// called.
+#[derive(Debug)]
+#[must_use = "an update does nothing until `send` is called"]
+pub struct Update<'a> {
+    hub: &'a mut Hub,
+    device: &'a mut Device,
+    custom_name: Option<String>,
+    is_on: Option<bool>,
+    light_level: Option<u8>,
+    color_temperature: Option<u16>,
+    hue_saturation: Option<(f64, f64)>,
+    startup_on_off: Option<Startup>,
+    blinds_target_level: Option<u8>,
+    transition: Option<Duration>,
+}
+
+impl<'a> Update<'a> {
+    pub(crate) fn new(hub: &'a mut Hub, device: &'a mut Device) -> Self {
+        Self {
+            hub,
+            device,
+            custom_name: None,
+            is_on: None,
+            light_level: None,
+            color_temperature: None,
+            hue_saturation: None,
+            startup_on_off: None,
+            blinds_target_level: None,
+            transition: None,
+        }
+    }
+
+    /// Rename the [`Device`].
+    pub fn custom_name(mut self, name: &str) -> Self {
+        self.custom_name = Some(name.to_string());
+        self
+    }
+
+    /// Turn the [`Device`] on or off.
+    pub fn on(mut self, is_on: bool) -> Self {
+        self.is_on = Some(is_on);
+        self
+    }
+
+    /// Set the light level between 0 and 100.
+    pub fn light_level(mut self, level: u8) -> Self {
+        self.light_level = Some(level);
+        self
+    }
+
+    /// Set the color temperature in Kelvin, within the light's min and max.
+    pub fn color_temperature(mut self, temperature: u16) -> Self {
+        self.color_temperature = Some(temperature);
+        self
+    }
+
+    /// Set hue between 0.0 and 360.0 and saturation between 0.0 and 1.0.
+    pub fn hue_saturation(mut self, hue: f64, saturation: f64) -> Self {
+        self.hue_saturation = Some((hue, saturation));
+        self
+    }
+
+    /// Set the startup behaviour.
+    pub fn startup_behaviour(mut self, behaviour: Startup) -> Self {
+        self.startup_on_off = Some(behaviour);
+        self
+    }
+
+    /// Move blinds to a target level between 0 and 100.
+    pub fn target_level(mut self, level: u8) -> Self {
+        self.blinds_target_level = Some(level);
+        self
+    }
+
+    /// Let the hub transition to the new values over the given duration instead of changing them
+    /// instantly. Devices that don't support a transition time ignore it, use [`Update::fade`]
+    /// for those.
+    pub fn transition(mut self, duration: Duration) -> Self {
+        self.transition = Some(duration);
+        self
+    }
+
+    /// Validate every attribute against the capabilities and ranges of the [`Device`] and send
+    /// them in a single request. Nothing is sent if any attribute is invalid. The [`Device`] is
+    /// only updated once the [`Hub`] has accepted the change.
+    pub async fn send(self) -> anyhow::Result<()> {
+        let attributes = self.validate()?;
+
+        if attributes.is_empty() {
+            return Ok(());
+        }
+
+        self.hub
+            .patch_attributes(&self.device.inner().id, &attributes, self.transition)
+            .await?;
+
+        self.device.merge_attributes(&attributes)?;
+
+        if let Device::Light(light) = self.device {
+            if self.color_temperature.is_some() {
+                light.attributes.color_mode = Some(ColorMode::Temperature);
+            } else if self.hue_saturation.is_some() {
+                light.attributes.color_mode = Some(ColorMode::Color);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gradually change light level, color temperature and hue and saturation from the current
+    /// values of the [`Device`] to the new ones over the given duration. Intermediate values are
+    /// sent at most every 200 ms, the first one right away and the last one once the duration
+    /// has passed. A light that's turned on is turned on with the first step and a light that's
+    /// turned off is turned off with the last one, anything else that doesn't fade is sent with
+    /// the first step. Everything is validated before the first step is sent. A duration too
+    /// short for more than one step sends the new values right away.
+    pub async fn fade(self, duration: Duration) -> anyhow::Result<()> {
+        self.validate()?;
+
+        let steps = fade_steps(duration);
+
+        if steps < 2 {
+            return self.send().await;
+        }
+
+        let Device::Light(light) = &*self.device else {
+            return self.send().await;
+        };
+
+        let start_level = light.attributes.light_level;
+        let start_temperature = light.attributes.color_temperature;
+        let start_hue_saturation = light
+            .attributes
+            .color_hue
+            .zip(light.attributes.color_saturation);
+
+        // The first tick completes immediately, so the first step is sent right away and the
+        // last one at the end of the duration.
+        let mut interval = tokio::time::interval(duration / (steps - 1));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        for step in 1..=steps {
+            let progress = f64::from(step) / f64::from(steps);
+            let (first, last) = (step == 1, step == steps);
+
+            let mut update = Update::new(&mut *self.hub, &mut *self.device);
+
+            if first {
+                update.custom_name = self.custom_name.clone();
+                update.startup_on_off = self.startup_on_off.clone();
+                update.blinds_target_level = self.blinds_target_level;
+            }
+
+            update.is_on = self
+                .is_on
+                .filter(|is_on| if *is_on { first } else { last });
+
+            update.light_level = self.light_level.map(|level| {
+                let start = f64::from(start_level.unwrap_or(level));
+                interpolate(start, f64::from(level), progress).round() as u8
+            });
+
+            update.color_temperature = self.color_temperature.map(|temperature| {
+                let start = f64::from(start_temperature.unwrap_or(temperature));
+                interpolate(start, f64::from(temperature), progress).round() as u16
+            });
+
+            update.hue_saturation = self.hue_saturation.map(|(hue, saturation)| {
+                let (start_hue, start_saturation) =
+                    start_hue_saturation.unwrap_or((hue, saturation));
+
+                (
+                    interpolate_hue(start_hue, hue, progress),
+                    interpolate(start_saturation, saturation, progress),
+                )
+            });
+
+            interval.tick().await;
+            update.send().await?;
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> anyhow::Result<Map<String, Value>> {
+        let mut attributes = Map::new();
+        let device = &*self.device;
+
+        if let Some(name) = &self.custom_name {
+            if !device.can_receive(&[Capability::CustomName]) {
+                anyhow::bail!("device cannot change name");
+            }
+
+            attributes.insert("customName".into(), name.as_str().into());
+        }
+
+        if let Some(is_on) = self.is_on {
+            if device.is_on().is_none() || !device.can_receive(&[Capability::IsOn]) {
+                anyhow::bail!("device cannot be turned on or off");
+            }
+
+            attributes.insert("isOn".into(), is_on.into());
+        }
+
+        if let Some(behaviour) = &self.startup_on_off {
+            if !matches!(device, Device::Light(_) | Device::Outlet(_)) {
+                anyhow::bail!("device has no startup behaviour");
+            }
+
+            attributes.insert("startupOnOff".into(), serde_json::to_value(behaviour)?);
+        }
+
+        if let Some(level) = self.blinds_target_level {
+            if !matches!(device, Device::Blinds(_))
+                || !device.can_receive(&[Capability::BlindsState])
+            {
+                anyhow::bail!("device cannot be change for blind state");
+            }
+
+            if level > 100 {
+                anyhow::bail!("level must be between 0.0 -> 100.0");
+            }
+
+            attributes.insert("blindsTargetLevel".into(), level.into());
+        }
+
+        let light_attributes = self.light_level.is_some()
+            || self.color_temperature.is_some()
+            || self.hue_saturation.is_some();
+
+        if !light_attributes {
+            return Ok(attributes);
+        }
+
+        let Device::Light(light) = device else {
+            anyhow::bail!("device is not a light");
+        };
+
+        if let Some(level) = self.light_level {
+            if !device.can_receive(&[Capability::LightLevel]) {
+                anyhow::bail!("device cannot set light level");
+            }
+
+            if level > 100 {
+                anyhow::bail!("level must be between 0.0 -> 100.0");
+            }
+
+            attributes.insert("lightLevel".into(), level.into());
+        }
+
+        if self.color_temperature.is_some() && self.hue_saturation.is_some() {
+            anyhow::bail!("cannot set both color temperature and hue and saturation");
+        }
+
+        if let Some(temperature) = self.color_temperature {
+            if !device.can_receive(&[Capability::ColorTemperature]) {
+                anyhow::bail!("device cannot set color temperature");
+            }
+
+            let min = light
+                .attributes
+                .color_temperature_min
+                .ok_or_else(|| anyhow::anyhow!("device has no min temperature value"))?;
+            let max = light
+                .attributes
+                .color_temperature_max
+                .ok_or_else(|| anyhow::anyhow!("device has no max temperature value"))?;
+
+            if !(max..=min).contains(&temperature) {
+                anyhow::bail!("color temperature {temperature} not within {min} -> {max}");
+            }
+
+            attributes.insert("colorTemperature".into(), temperature.into());
+        }
+
+        if let Some((hue, saturation)) = self.hue_saturation {
+            if !device.can_receive(&[Capability::ColorHue, Capability::ColorSaturation]) {
+                anyhow::bail!("device cannot be change for hue and saturation");
+            }
+
+            if !(0f64..=360f64).contains(&hue) {
+                anyhow::bail!("hue must be between 0.0 -> 360.0");
+            }
+
+            if !(0f64..=1f64).contains(&saturation) {
+                anyhow::bail!("saturation must be between 0.0 -> 1.0");
+            }
+
+            attributes.insert("colorHue".into(), hue.into());
+            attributes.insert("colorSaturation".into(), saturation.into());
+        }
+
+        Ok(attributes)
+    }
+}
+
+/// Number of steps [`Update::fade`] takes over the given duration, at most 1000.
+fn fade_steps(duration: Duration) -> u32 {
+    (duration.as_millis() / FADE_INTERVAL.as_millis()).min(1000) as u32
+}
+
+/// Interpolate a hue the shortest way around the color wheel.
+fn interpolate_hue(start: f64, end: f64, progress: f64) -> f64 {
+    let delta = (end - start + 540.0).rem_euclid(360.0) - 180.0;
+
+    (start + delta * progress).rem_euclid(360.0)
+}
+
+fn interpolate(start: f64, end: f64, progress: f64) -> f64 {
+    start + (end - start) * progress
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::traits::DirigeraExt;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn hub() -> Hub {
+        Hub::new(&Config {
+            ip_address: std::net::Ipv4Addr::LOCALHOST,
+            token: "token".to_string(),
+        })
+        .unwrap()
+    }
+
+    fn light() -> Device {
+        serde_json::from_str(include_str!("../tests/fixtures/light.json")).unwrap()
+    }
+
+    #[test]
+    fn validate_light() {
+        let (mut hub, mut light) = (hub(), light());
+
+        let attributes = Update::new(&mut hub, &mut light)
+            .on(true)
+            .light_level(40)
+            .color_temperature(3000)
+            .validate()
+            .unwrap();
+
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(attributes["isOn"], true);
+        assert_eq!(attributes["lightLevel"], 40);
+        assert_eq!(attributes["colorTemperature"], 3000);
+    }
+
+    #[test]
+    fn validate_empty() {
+        let (mut hub, mut light) = (hub(), light());
+
+        assert!(Update::new(&mut hub, &mut light).validate().unwrap().is_empty());
+    }
+
+    #[test]
+    fn validate_out_of_range() {
+        let (mut hub, mut light) = (hub(), light());
+
+        // The light's min is 4000 K and its max 2202 K.
+        for temperature in [2201, 4001] {
+            let update = Update::new(&mut hub, &mut light).color_temperature(temperature);
+            assert!(update.validate().is_err());
+        }
+
+        assert!(Update::new(&mut hub, &mut light).light_level(101).validate().is_err());
+        assert!(Update::new(&mut hub, &mut light)
+            .hue_saturation(361.0, 0.5)
+            .validate()
+            .is_err());
+        assert!(Update::new(&mut hub, &mut light)
+            .hue_saturation(120.0, 1.5)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn validate_conflicting_colors() {
+        let (mut hub, mut light) = (hub(), light());
+
+        let update = Update::new(&mut hub, &mut light)
+            .color_temperature(3000)
+            .hue_saturation(120.0, 0.5);
+
+        assert!(update.validate().is_err());
+    }
+
+    #[test]
+    fn validate_unsupported() {
+        let mut hub = hub();
+        let mut outlet: Device =
+            serde_json::from_str(include_str!("../tests/fixtures/outlet.json")).unwrap();
+
+        assert!(Update::new(&mut hub, &mut outlet).light_level(40).validate().is_err());
+        assert!(Update::new(&mut hub, &mut outlet).target_level(40).validate().is_err());
+        assert!(Update::new(&mut hub, &mut outlet).on(false).validate().is_ok());
+    }
+
+    #[test]
+    fn fade_step_count() {
+        assert_eq!(fade_steps(Duration::from_secs(1)), 5);
+        assert_eq!(fade_steps(Duration::from_millis(1099)), 5);
+        assert_eq!(fade_steps(Duration::from_secs(3600)), 1000);
+
+        // Fewer than two steps, the new values are sent right away.
+        assert_eq!(fade_steps(Duration::from_millis(399)), 1);
+        assert_eq!(fade_steps(Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn hue_shortest_way() {
+        // Across 0 in both directions.
+        assert!((interpolate_hue(350.0, 10.0, 0.5) - 0.0).abs() < EPSILON);
+        assert!((interpolate_hue(350.0, 10.0, 0.25) - 355.0).abs() < EPSILON);
+        assert!((interpolate_hue(10.0, 350.0, 0.25) - 5.0).abs() < EPSILON);
+
+        // Without wrapping.
+        assert!((interpolate_hue(100.0, 200.0, 0.5) - 150.0).abs() < EPSILON);
+        assert!((interpolate_hue(200.0, 100.0, 0.5) - 150.0).abs() < EPSILON);
+
+        // Ends exactly at the target.
+        assert!((interpolate_hue(350.0, 10.0, 1.0) - 10.0).abs() < EPSILON);
+        assert!((interpolate_hue(10.0, 350.0, 1.0) - 350.0).abs() < EPSILON);
+    }
+}
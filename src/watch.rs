@@ -0,0 +1,194 @@
+//! Where the event WebSocket isn't available the [`Hub`] can be polled instead. Each poll is
+//! compared with the previous one and every difference is reported as a [`WatchEvent`] through
+//! the stream returned by [`Hub::watch`](crate::Hub::watch).
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use futures::Stream;
+use tokio::time::{Interval, MissedTickBehavior};
+
+use crate::diff::Change;
+use crate::traits::DirigeraExt;
+use crate::Device;
+use crate::Error;
+use crate::Hub;
+
+/// A difference between two polls of the [`Hub`].
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    /// A [`Device`] that wasn't part of the previous poll.
+    Added(Box<Device>),
+    /// The id of a [`Device`] that is no longer known by the [`Hub`].
+    Removed(String),
+    /// A single field of the [`Device`] with the given id changed.
+    Changed { id: String, change: Change },
+}
+
+/// State behind [`Hub::watch`](crate::Hub::watch).
+pub(crate) struct Watcher {
+    hub: Hub,
+    period: Duration,
+    interval: Option<Interval>,
+    snapshot: Option<HashMap<String, Device>>,
+    pending: VecDeque<WatchEvent>,
+}
+
+impl Watcher {
+    pub(crate) fn new(hub: Hub, period: Duration) -> Self {
+        Self {
+            hub,
+            period,
+            interval: None,
+            snapshot: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn into_stream(self) -> impl Stream<Item = Result<WatchEvent, Error>> {
+        futures::stream::unfold(self, |mut watcher| async move {
+            let item = watcher.next().await;
+            Some((item, watcher))
+        })
+    }
+
+    async fn next(&mut self) -> Result<WatchEvent, Error> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+
+            // The interval is created on first poll since it needs a running runtime.
+            let period = self.period;
+            self.interval
+                .get_or_insert_with(|| {
+                    let mut interval = tokio::time::interval(period);
+                    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                    interval
+                })
+                .tick()
+                .await;
+
+            let devices = self
+                .hub
+                .list()
+                .await?
+                .into_iter()
+                .map(|device| (device.inner().id.clone(), device))
+                .collect::<HashMap<_, _>>();
+
+            if let Some(previous) = self.snapshot.as_ref() {
+                self.pending = compare(previous, &devices);
+            }
+
+            self.snapshot = Some(devices);
+        }
+    }
+}
+
+/// Fields that change on nearly every poll of a reachable [`Device`] and aren't reported.
+const IGNORED: &[&str] = &["last_seen"];
+
+fn compare(
+    previous: &HashMap<String, Device>,
+    current: &HashMap<String, Device>,
+) -> VecDeque<WatchEvent> {
+    let mut events = VecDeque::new();
+
+    for (id, device) in current {
+        let Some(old) = previous.get(id) else {
+            events.push_back(WatchEvent::Added(Box::new(device.clone())));
+            continue;
+        };
+
+        let changes = old
+            .diff(device)
+            .into_iter()
+            .filter(|change| !IGNORED.contains(&change.path.as_str()));
+
+        for change in changes {
+            events.push_back(WatchEvent::Changed {
+                id: id.clone(),
+                change,
+            });
+        }
+    }
+
+    for id in previous.keys().filter(|id| !current.contains_key(*id)) {
+        events.push_back(WatchEvent::Removed(id.clone()));
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devices(devices: &[&Device]) -> HashMap<String, Device> {
+        devices
+            .iter()
+            .map(|device| (device.inner().id.clone(), (*device).clone()))
+            .collect()
+    }
+
+    fn light() -> Device {
+        serde_json::from_str(include_str!("../tests/fixtures/light.json")).unwrap()
+    }
+
+    fn outlet() -> Device {
+        serde_json::from_str(include_str!("../tests/fixtures/outlet.json")).unwrap()
+    }
+
+    #[test]
+    fn unchanged() {
+        let previous = devices(&[&light(), &outlet()]);
+
+        assert!(compare(&previous, &previous.clone()).is_empty());
+    }
+
+    #[test]
+    fn added_and_removed() {
+        let events = compare(&devices(&[&light()]), &devices(&[&outlet()]));
+
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            WatchEvent::Added(device) if device.inner().id == outlet().inner().id
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            WatchEvent::Removed(id) if *id == light().inner().id
+        )));
+    }
+
+    #[test]
+    fn changed() {
+        let mut changed = light();
+        changed.inner_mut().is_reachable = false;
+        changed
+            .merge_attributes(serde_json::json!({ "isOn": false }).as_object().unwrap())
+            .unwrap();
+
+        let events = compare(&devices(&[&light()]), &devices(&[&changed]));
+        let mut paths = events
+            .iter()
+            .map(|event| match event {
+                WatchEvent::Changed { id, change } => {
+                    assert_eq!(*id, light().inner().id);
+                    change.to_string()
+                }
+                event => panic!("unexpected {event:?}"),
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(paths, ["attributes.is_on: true -> false", "is_reachable: true -> false"]);
+    }
+
+    #[test]
+    fn last_seen_is_ignored() {
+        let mut seen = light();
+        seen.inner_mut().last_seen += chrono::Duration::seconds(30);
+
+        assert!(compare(&devices(&[&light()]), &devices(&[&seen])).is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+//! Every model serializes to the same JSON as read from the hub, see the fixtures in
+//! `tests/fixtures`.
+use dirigera::{Device, Scene};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+fn assert_roundtrip<T: Serialize + DeserializeOwned>(fixture: &str) {
+    let original: serde_json::Value = serde_json::from_str(fixture).unwrap();
+    let parsed: T = serde_json::from_str(fixture).unwrap();
+
+    // Values compare numbers by representation, so `30` and `30.0` are different.
+    assert_eq!(serde_json::to_value(&parsed).unwrap(), original);
+}
+
+#[test]
+fn light() {
+    assert_roundtrip::<Device>(include_str!("fixtures/light.json"));
+}
+
+#[test]
+fn outlet() {
+    assert_roundtrip::<Device>(include_str!("fixtures/outlet.json"));
+}
+
+#[test]
+fn environment_sensor() {
+    assert_roundtrip::<Device>(include_str!("fixtures/environment_sensor.json"));
+}
+
+#[test]
+fn scene() {
+    assert_roundtrip::<Scene>(include_str!("fixtures/scene.json"));
+}
+
+#[test]
+fn invalid_optional_date() {
+    let mut scene: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/scene.json")).unwrap();
+    scene["lastCompleted"] = "yesterday".into();
+
+    let scene: Scene = serde_json::from_value(scene).unwrap();
+    assert_eq!(scene.inner().last_completed, None);
+}
+
+#[test]
+fn null_optional_date() {
+    let mut scene: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/scene.json")).unwrap();
+    scene["lastCompleted"] = serde_json::Value::Null;
+
+    let scene: Scene = serde_json::from_value(scene).unwrap();
+    assert_eq!(scene.inner().last_completed, None);
+}